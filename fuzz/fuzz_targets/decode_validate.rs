@@ -0,0 +1,97 @@
+//! Feeds arbitrary bytes through the same decode and validation path as `/rpc`
+//! and `/decode`, including the admission priority and analysis `/rpc` runs on
+//! unsanitized input before validation. Any panic is a bug: malformed input
+//! must surface as an error.
+#![no_main]
+
+use base64::Engine;
+use libfuzzer_sys::fuzz_target;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_service::access_policy::{AccessListConfig, AccessPolicy, AccessPolicyConfig};
+use solana_transaction_service::admission_queue::AdmissionPriority;
+use solana_transaction_service::compute_budget_policy::ComputeBudgetPolicy;
+use solana_transaction_service::decoders::token::TOKEN_PROGRAM_ID;
+use solana_transaction_service::decoders::DecoderRegistry;
+use solana_transaction_service::tip_accounts::TIP_ACCOUNTS;
+use solana_transaction_service::transaction_analyzer;
+use solana_transaction_service::transaction_inspector;
+use solana_transaction_service::validation::{
+    AccessPolicyStage, ComputeBudgetStage, DecodeStage, SignatureStage, SizeStage, TipStage, ValidationPipeline,
+};
+use solana_sdk::transaction::Transaction;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+struct Harness {
+    pipeline: ValidationPipeline,
+    decoders: DecoderRegistry,
+    tip_stage: TipStage,
+    tip_pubkeys: Vec<Pubkey>,
+}
+
+/// Deny lists for every kind of key and allowlists for programs, accounts and
+/// writable accounts, so policy lookups run against populated sets.
+fn access_policy(tip_pubkeys: &[Pubkey]) -> AccessPolicy {
+    let keys = |keys: &[Pubkey]| keys.iter().map(Pubkey::to_string).collect::<Vec<_>>();
+    let programs = [
+        solana_sdk::system_program::id(),
+        solana_sdk::compute_budget::id(),
+        TOKEN_PROGRAM_ID,
+    ];
+    let denied = [Pubkey::new_from_array([7; 32]), Pubkey::new_from_array([9; 32])];
+    let list = |allow: &[Pubkey]| AccessListConfig { allow: keys(allow), deny: keys(&denied) };
+    let config = AccessPolicyConfig {
+        programs: list(&programs),
+        accounts: AccessListConfig { allow: keys(tip_pubkeys), deny: keys(&denied[..1]) },
+        signers: list(&[]),
+        writable: list(tip_pubkeys),
+    };
+    AccessPolicy::from_config(&config).expect("fuzz access policy is valid")
+}
+
+fn harness() -> &'static Harness {
+    static HARNESS: OnceLock<Harness> = OnceLock::new();
+    HARNESS.get_or_init(|| {
+        let tip_pubkeys: Vec<Pubkey> = TIP_ACCOUNTS.iter().filter_map(|s| Pubkey::from_str(s).ok()).collect();
+        let compute_budget_policy = ComputeBudgetPolicy {
+            min_unit_price: Some(1),
+            max_unit_limit: Some(1_400_000),
+            reject_duplicates: true,
+            priority_fee_counts_toward_tip: true,
+        };
+        let tip_stage = TipStage {
+            tip_pubkeys: tip_pubkeys.clone(),
+            min_tip_lamports: 1_000,
+            require_last: true,
+            compute_budget_policy: compute_budget_policy.clone(),
+        };
+        // The blockhash stage needs RPC and is left out.
+        let pipeline = ValidationPipeline::new(vec![
+            Box::new(DecodeStage),
+            Box::new(SizeStage { max_bytes: solana_sdk::packet::PACKET_DATA_SIZE }),
+            Box::new(SignatureStage { verify: false }),
+            Box::new(tip_stage.clone()),
+            Box::new(ComputeBudgetStage { policy: compute_budget_policy }),
+            Box::new(AccessPolicyStage { policy: Arc::new(access_policy(&tip_pubkeys)) }),
+        ]);
+        Harness {
+            pipeline,
+            decoders: DecoderRegistry::with_builtin(),
+            tip_stage,
+            tip_pubkeys,
+        }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let harness = harness();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let _ = AdmissionPriority::from_encoded(&encoded, &harness.tip_pubkeys);
+    if let Ok(tx) = bincode::deserialize::<Transaction>(data) {
+        let _ = transaction_analyzer::analyze(&tx, &harness.tip_pubkeys);
+    }
+    let _ = harness.pipeline.run(&encoded);
+    if let Ok(tx) = transaction_inspector::decode_versioned(&encoded, "base64") {
+        let _ = transaction_inspector::inspect(&tx, &harness.decoders, &harness.tip_stage);
+    }
+});
@@ -0,0 +1,342 @@
+use crate::errors::ServiceError;
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::collections::HashSet;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+/// Allow and deny lists for one kind of key. An empty `allow` list allows everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessListConfig {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+/// On-disk format of the access policy file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessPolicyConfig {
+    /// Programs invoked by any instruction.
+    pub programs: AccessListConfig,
+    /// Every account referenced by the message. Invoked programs (see
+    /// `programs`) and sysvars are exempt from the allowlist but not the denylist.
+    pub accounts: AccessListConfig,
+    pub signers: AccessListConfig,
+    pub writable: AccessListConfig,
+}
+
+#[derive(Debug, Default)]
+struct AccessList {
+    allow: HashSet<Pubkey>,
+    deny: HashSet<Pubkey>,
+}
+
+impl AccessList {
+    fn parse(kind: &str, config: &AccessListConfig) -> Result<Self, ServiceError> {
+        let parse_all = |keys: &[String]| {
+            keys.iter()
+                .map(|k| {
+                    Pubkey::from_str(k.trim())
+                        .map_err(|e| ServiceError::InvalidPolicy(format!("{}: invalid pubkey {}: {}", kind, k, e)))
+                })
+                .collect::<Result<HashSet<_>, _>>()
+        };
+        Ok(Self {
+            allow: parse_all(&config.allow)?,
+            deny: parse_all(&config.deny)?,
+        })
+    }
+
+    fn check<'a>(&self, kind: &'static str, keys: impl IntoIterator<Item = &'a Pubkey>) -> Result<(), PolicyViolation> {
+        self.check_allow_exempt(kind, keys, |_| false)
+    }
+
+    /// Like `check`, but keys matching `exempt` only skip the allowlist; the
+    /// denylist still applies to them.
+    fn check_allow_exempt<'a>(
+        &self,
+        kind: &'static str,
+        keys: impl IntoIterator<Item = &'a Pubkey>,
+        exempt: impl Fn(&Pubkey) -> bool,
+    ) -> Result<(), PolicyViolation> {
+        for key in keys {
+            if self.deny.contains(key) {
+                return Err(PolicyViolation::Denied { kind, pubkey: key.to_string() });
+            }
+            if !self.allow.is_empty() && !self.allow.contains(key) && !exempt(key) {
+                return Err(PolicyViolation::NotAllowed { kind, pubkey: key.to_string() });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct AccessRules {
+    programs: AccessList,
+    accounts: AccessList,
+    signers: AccessList,
+    writable: AccessList,
+}
+
+impl AccessRules {
+    fn from_config(config: &AccessPolicyConfig) -> Result<Self, ServiceError> {
+        Ok(Self {
+            programs: AccessList::parse("programs", &config.programs)?,
+            accounts: AccessList::parse("accounts", &config.accounts)?,
+            signers: AccessList::parse("signers", &config.signers)?,
+            writable: AccessList::parse("writable", &config.writable)?,
+        })
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[error("{kind} entry {pubkey} is denied by policy")]
+    Denied { kind: &'static str, pubkey: String },
+
+    #[error("{kind} entry {pubkey} is not in the policy allowlist")]
+    NotAllowed { kind: &'static str, pubkey: String },
+}
+
+impl PolicyViolation {
+    /// JSON-RPC error code returned for policy rejections.
+    pub fn code(&self) -> i64 {
+        -32010
+    }
+}
+
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    timestamp: String,
+    signature: &'a str,
+    payer: Option<String>,
+    reason: String,
+}
+
+/// Program and account allow/deny lists, reloaded when the backing file changes.
+pub struct AccessPolicy {
+    path: Option<String>,
+    audit_log_path: Option<String>,
+    rules: RwLock<AccessRules>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl AccessPolicy {
+    /// A policy that allows everything.
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            audit_log_path: None,
+            rules: RwLock::new(AccessRules::default()),
+            modified: RwLock::new(None),
+        }
+    }
+
+    /// A fixed policy built from `config`, with no file to reload.
+    pub fn from_config(config: &AccessPolicyConfig) -> Result<Self, ServiceError> {
+        let policy = Self::disabled();
+        *policy.rules.write().expect("access policy lock poisoned") = AccessRules::from_config(config)?;
+        Ok(policy)
+    }
+
+    pub fn load(path: &str, audit_log_path: Option<String>) -> Result<Self, ServiceError> {
+        let policy = Self {
+            path: Some(path.to_string()),
+            audit_log_path,
+            rules: RwLock::new(AccessRules::default()),
+            modified: RwLock::new(None),
+        };
+        policy.reload()?;
+        Ok(policy)
+    }
+
+    /// Re-reads the policy file. On error the previous rules stay in effect.
+    pub fn reload(&self) -> Result<(), ServiceError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| ServiceError::InvalidPolicy(format!("Failed to read {}: {}", path, e)))?;
+        let config: AccessPolicyConfig = serde_json::from_str(&json)
+            .map_err(|e| ServiceError::InvalidPolicy(format!("Failed to parse {}: {}", path, e)))?;
+        let rules = AccessRules::from_config(&config)?;
+        info!(
+            "Loaded access policy from {} (programs allow={} deny={}, accounts allow={} deny={}, signers allow={} deny={}, writable allow={} deny={})",
+            path,
+            rules.programs.allow.len(),
+            rules.programs.deny.len(),
+            rules.accounts.allow.len(),
+            rules.accounts.deny.len(),
+            rules.signers.allow.len(),
+            rules.signers.deny.len(),
+            rules.writable.allow.len(),
+            rules.writable.deny.len()
+        );
+        *self.rules.write().expect("access policy lock poisoned") = rules;
+        *self.modified.write().expect("access policy lock poisoned") = modified;
+        Ok(())
+    }
+
+    /// Checks every program, account, signer and writable account of `message`.
+    /// Invoked programs and sysvars are exempt from the `accounts` allowlist, so
+    /// it does not need to repeat them, but the `accounts` denylist covers every key.
+    pub fn check(&self, message: &Message) -> Result<(), PolicyViolation> {
+        let rules = self.rules.read().expect("access policy lock poisoned");
+        let keys = &message.account_keys;
+        let programs: HashSet<&Pubkey> = message
+            .instructions
+            .iter()
+            .filter_map(|ix| keys.get(ix.program_id_index as usize))
+            .collect();
+        rules.programs.check("program", programs.iter().copied())?;
+        rules.accounts.check_allow_exempt("account", keys, |k| programs.contains(k) || sysvar::is_sysvar_id(k))?;
+        rules.signers.check(
+            "signer",
+            keys.iter().enumerate().filter(|(i, _)| message.is_signer(*i)).map(|(_, k)| k),
+        )?;
+        rules.writable.check(
+            "writable account",
+            keys.iter().enumerate().filter(|(i, _)| message.is_writable(*i)).map(|(_, k)| k),
+        )?;
+        Ok(())
+    }
+
+    /// Records a rejection in the log and, when configured, the audit file.
+    pub fn audit_rejection(&self, signature: &str, message: &Message, violation: &PolicyViolation) {
+        let payer = message.account_keys.first().map(|k| k.to_string());
+        warn!(
+            target: "audit",
+            "Policy rejection: signature={} payer={} reason={}",
+            signature,
+            payer.as_deref().unwrap_or("-"),
+            violation
+        );
+        let Some(path) = &self.audit_log_path else {
+            return;
+        };
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            signature,
+            payer,
+            reason: violation.to_string(),
+        };
+        let result = serde_json::to_string(&entry)
+            .map_err(std::io::Error::other)
+            .and_then(|line| {
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{}", line)
+            });
+        if let Err(e) = result {
+            error!("Failed to write policy audit entry to {}: {}", path, e);
+        }
+    }
+
+    /// Polls the policy file and reloads it whenever its modification time changes.
+    pub fn spawn_reloader(self: &Arc<Self>, interval: Duration) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let policy = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                if modified == *policy.modified.read().expect("access policy lock poisoned") {
+                    continue;
+                }
+                if let Err(e) = policy.reload() {
+                    error!("Access policy reload failed, keeping previous rules: {}", e);
+                    *policy.modified.write().expect("access policy lock poisoned") = modified;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::system_instruction;
+
+    fn policy(config: AccessPolicyConfig) -> AccessPolicy {
+        AccessPolicy::from_config(&config).unwrap()
+    }
+
+    fn list(keys: &[Pubkey]) -> AccessListConfig {
+        AccessListConfig { allow: keys.iter().map(|k| k.to_string()).collect(), deny: vec![] }
+    }
+
+    #[test]
+    fn accounts_allowlist_skips_programs_and_sysvars() {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let invoke = Instruction::new_with_bytes(
+            program,
+            &[],
+            vec![AccountMeta::new_readonly(sysvar::clock::id(), false)],
+        );
+        let message = Message::new(
+            &[system_instruction::transfer(&payer, &recipient, 1), invoke],
+            Some(&payer),
+        );
+
+        let allowed = policy(AccessPolicyConfig { accounts: list(&[payer, recipient]), ..Default::default() });
+        assert_eq!(allowed.check(&message), Ok(()));
+
+        let restricted = policy(AccessPolicyConfig { accounts: list(&[payer]), ..Default::default() });
+        assert_eq!(
+            restricted.check(&message),
+            Err(PolicyViolation::NotAllowed { kind: "account", pubkey: recipient.to_string() })
+        );
+
+        // Programs still answer to the programs list.
+        let programs = policy(AccessPolicyConfig {
+            accounts: list(&[payer, recipient]),
+            programs: list(&[solana_sdk::system_program::id()]),
+            ..Default::default()
+        });
+        assert_eq!(
+            programs.check(&message),
+            Err(PolicyViolation::NotAllowed { kind: "program", pubkey: program.to_string() })
+        );
+    }
+
+    #[test]
+    fn accounts_denylist_covers_programs_and_sysvars() {
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let invoke = Instruction::new_with_bytes(
+            program,
+            &[],
+            vec![AccountMeta::new_readonly(sysvar::clock::id(), false)],
+        );
+        let message = Message::new(&[invoke], Some(&payer));
+        let deny = |key: Pubkey| AccessPolicyConfig {
+            accounts: AccessListConfig { allow: vec![], deny: vec![key.to_string()] },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy(deny(program)).check(&message),
+            Err(PolicyViolation::Denied { kind: "account", pubkey: program.to_string() })
+        );
+        assert_eq!(
+            policy(deny(sysvar::clock::id())).check(&message),
+            Err(PolicyViolation::Denied { kind: "account", pubkey: sysvar::clock::id().to_string() })
+        );
+    }
+}
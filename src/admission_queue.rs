@@ -0,0 +1,361 @@
+use crate::metrics::Metrics;
+use crate::rate_limiter::RateLimiter;
+use crate::decoders::compute_budget::ComputeBudgetSettings;
+use crate::transaction_analyzer;
+
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sanitize::Sanitize;
+use solana_sdk::transaction::Transaction;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use thiserror::Error;
+use tokio::sync::{oneshot, Notify};
+use tracing::info;
+
+/// Ordering key for queued requests: higher tips win, compute unit price breaks ties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AdmissionPriority {
+    pub tip_lamports: u64,
+    pub compute_unit_price: u64,
+}
+
+impl AdmissionPriority {
+    /// Priority of a base64 wire transaction, computed before validation by
+    /// `/rpc` and `/sendTransaction`. Undecodable payloads get the default.
+    pub fn from_encoded(encoded: &str, tip_pubkeys: &[Pubkey]) -> Self {
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<Transaction>(&bytes).ok())
+            .map(|tx| Self::from_transaction(&tx, tip_pubkeys))
+            .unwrap_or_default()
+    }
+
+    /// Priority paid by `tx`. Runs before validation on untrusted bytes, so only
+    /// sanitized transactions with valid signatures earn more than the default;
+    /// otherwise an unsigned or forged tip would buy a better queue position.
+    pub fn from_transaction(tx: &Transaction, tip_pubkeys: &[Pubkey]) -> Self {
+        if tx.sanitize().is_err() || tx.verify().is_err() {
+            return Self::default();
+        }
+        let budget = ComputeBudgetSettings::from_instructions(&tx.message.account_keys, &tx.message.instructions);
+        Self {
+            tip_lamports: transaction_analyzer::analyze(tx, tip_pubkeys)
+                .map(|analysis| analysis.tip_lamports)
+                .unwrap_or(0),
+            compute_unit_price: budget.unit_price_micro_lamports.unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AdmissionError {
+    #[error("admission queue full")]
+    QueueFull,
+
+    #[error("evicted from admission queue by a higher paying transaction")]
+    Evicted,
+
+    #[error("timed out after {0}ms in admission queue")]
+    TimedOut(u128),
+}
+
+type WaiterKey = (AdmissionPriority, Reverse<u64>);
+
+struct QueueInner {
+    waiters: BTreeMap<WaiterKey, oneshot::Sender<()>>,
+    next_seq: u64,
+}
+
+/// Holds over-limit requests until the rate limiter has room, releasing the
+/// highest paying waiter first.
+pub struct AdmissionQueue {
+    rate_limiter: Arc<RateLimiter>,
+    metrics: Arc<Metrics>,
+    max_depth: usize,
+    max_wait: Duration,
+    inner: Mutex<QueueInner>,
+    notify: Notify,
+}
+
+impl AdmissionQueue {
+    pub fn new(
+        rate_limiter: Arc<RateLimiter>,
+        metrics: Arc<Metrics>,
+        max_depth: usize,
+        max_wait: Duration,
+    ) -> Self {
+        Self {
+            rate_limiter,
+            metrics,
+            max_depth,
+            max_wait,
+            inner: Mutex::new(QueueInner {
+                waiters: BTreeMap::new(),
+                next_seq: 0,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.inner.lock().expect("admission queue mutex poisoned").waiters.len()
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Free slots belong to queued waiters first; only an empty queue lets a
+    /// new arrival through directly.
+    fn try_admit_directly(&self) -> bool {
+        let inner = self.inner.lock().expect("admission queue mutex poisoned");
+        inner.waiters.is_empty() && self.rate_limiter.try_acquire()
+    }
+
+    /// Takes a rate limiter slot, queueing behind higher paying requests when
+    /// there are any. Returns the time spent queued, or `None` if the request
+    /// went straight through. `priority` is only awaited when the request has
+    /// to queue, so requests that go straight through never pay for it.
+    pub async fn admit(
+        &self,
+        priority: impl Future<Output = AdmissionPriority>,
+    ) -> Result<Option<Duration>, AdmissionError> {
+        if self.try_admit_directly() {
+            return Ok(None);
+        }
+        let priority = priority.await;
+        let enqueued_at = Instant::now();
+        let (tx, mut rx) = oneshot::channel();
+        let key = {
+            let mut inner = self.inner.lock().expect("admission queue mutex poisoned");
+            // A slot may have freed up while the priority was computed.
+            if inner.waiters.is_empty() && self.rate_limiter.try_acquire() {
+                return Ok(None);
+            }
+            let key = (priority, Reverse(inner.next_seq));
+            inner.next_seq += 1;
+            if inner.waiters.len() >= self.max_depth {
+                match inner.waiters.first_key_value() {
+                    Some((lowest, _)) if *lowest < key => {
+                        // Dropping the sender wakes the evicted waiter with an error.
+                        inner.waiters.pop_first();
+                    }
+                    _ => {
+                        self.metrics.record_rejected_full();
+                        return Err(AdmissionError::QueueFull);
+                    }
+                }
+            }
+            inner.waiters.insert(key, tx);
+            key
+        };
+        self.metrics.record_queued();
+        self.notify.notify_one();
+
+        let admitted = match tokio::time::timeout(self.max_wait, &mut rx).await {
+            Ok(result) => result.is_ok(),
+            Err(_) => {
+                let removed = self
+                    .inner
+                    .lock()
+                    .expect("admission queue mutex poisoned")
+                    .waiters
+                    .remove(&key)
+                    .is_some();
+                if removed {
+                    self.metrics.record_timed_out();
+                    return Err(AdmissionError::TimedOut(self.max_wait.as_millis()));
+                }
+                // The dispatcher picked this waiter just as it timed out; the
+                // slot was sent under the lock, so it is already in the channel.
+                rx.try_recv().is_ok()
+            }
+        };
+        if admitted {
+            let waited = enqueued_at.elapsed();
+            self.metrics.record_admitted(waited);
+            info!(
+                "Admitted from queue after {}ms (tip={} lamports, cu_price={})",
+                waited.as_millis(),
+                priority.tip_lamports,
+                priority.compute_unit_price
+            );
+            Ok(Some(waited))
+        } else {
+            self.metrics.record_evicted();
+            Err(AdmissionError::Evicted)
+        }
+    }
+
+    /// Hands one rate limiter slot to the best waiter still listening. The slot
+    /// is only taken once such a waiter exists, and is returned if the hand-off fails.
+    fn dispatch_one(&self) {
+        let mut inner = self.inner.lock().expect("admission queue mutex poisoned");
+        while inner.waiters.last_key_value().is_some_and(|(_, tx)| tx.is_closed()) {
+            inner.waiters.pop_last();
+        }
+        let Some(entry) = inner.waiters.last_entry() else {
+            return;
+        };
+        if !self.rate_limiter.try_acquire() {
+            return;
+        }
+        if entry.remove().send(()).is_err() {
+            self.rate_limiter.release();
+        }
+    }
+
+    /// Background loop handing freed rate limiter slots to the best queued waiter.
+    pub fn spawn_dispatcher(self: &Arc<Self>) {
+        let queue = Arc::clone(self);
+        tokio::spawn(async move {
+            info!(
+                "Admission queue dispatcher started (max_depth={}, max_wait={}ms)",
+                queue.max_depth,
+                queue.max_wait.as_millis()
+            );
+            loop {
+                if queue.depth() == 0 {
+                    queue.notify.notified().await;
+                    continue;
+                }
+                let wait = queue.rate_limiter.next_available_in();
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+                queue.dispatch_one();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use std::future::ready;
+
+    fn queue(tps: u32, max_depth: usize) -> Arc<AdmissionQueue> {
+        Arc::new(AdmissionQueue::new(
+            Arc::new(RateLimiter::new(tps)),
+            Arc::new(Metrics::new()),
+            max_depth,
+            Duration::from_secs(5),
+        ))
+    }
+
+    fn tip(lamports: u64) -> AdmissionPriority {
+        AdmissionPriority {
+            tip_lamports: lamports,
+            compute_unit_price: 0,
+        }
+    }
+
+    /// Waits for queued tasks to reach `depth`. With the clock paused the
+    /// runtime auto-advances through these sleeps when idle.
+    async fn wait_for_depth(queue: &AdmissionQueue, depth: usize) {
+        while queue.depth() != depth {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn arrivals_queue_behind_waiters_even_with_a_free_slot() {
+        let queue = queue(2, 10);
+        assert_eq!(queue.admit(ready(tip(0))).await.unwrap(), None);
+        queue.rate_limiter.try_acquire();
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.admit(ready(tip(1))).await }
+        });
+        wait_for_depth(&queue, 1).await;
+        // Let a slot free up: the new arrival must still not jump the queue.
+        tokio::time::advance(Duration::from_millis(1050)).await;
+        let arrival = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.admit(ready(tip(0))).await }
+        });
+        wait_for_depth(&queue, 2).await;
+        queue.spawn_dispatcher();
+        assert!(waiting.await.unwrap().unwrap().is_some());
+        assert!(arrival.await.unwrap().unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn priority_is_only_computed_for_queued_requests() {
+        let queue = queue(1, 10);
+        let direct = queue.admit(async { unreachable!("priority computed for a direct admission") });
+        assert_eq!(direct.await.unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn highest_tip_is_released_first() {
+        let queue = queue(1, 10);
+        assert_eq!(queue.admit(ready(tip(0))).await.unwrap(), None);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        for (depth, lamports) in [(1, 10), (2, 1_000), (3, 100)] {
+            let waiter = queue.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                waiter.admit(ready(tip(lamports))).await.unwrap();
+                order.lock().unwrap().push(lamports);
+            }));
+            wait_for_depth(&queue, depth).await;
+        }
+        queue.spawn_dispatcher();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![1_000, 100, 10]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abandoned_waiters_do_not_use_up_slots() {
+        let queue = queue(1, 10);
+        queue.rate_limiter.try_acquire();
+        let (tx, rx) = oneshot::channel();
+        queue.inner.lock().unwrap().waiters.insert((tip(5), Reverse(0)), tx);
+        drop(rx);
+        tokio::time::advance(Duration::from_millis(1050)).await;
+        queue.dispatch_one();
+        assert_eq!(queue.depth(), 0);
+        assert!(queue.rate_limiter.try_acquire(), "slot was spent on a dropped waiter");
+    }
+
+    #[test]
+    fn priority_counts_only_signed_sanitized_tips() {
+        let payer = Keypair::new();
+        let tip_account = Pubkey::new_unique();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_price(7),
+            system_instruction::transfer(&payer.pubkey(), &tip_account, 5_000),
+        ];
+        let signed = Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[&payer], Hash::default());
+        let paid = AdmissionPriority {
+            tip_lamports: 5_000,
+            compute_unit_price: 7,
+        };
+        assert_eq!(AdmissionPriority::from_transaction(&signed, &[tip_account]), paid);
+
+        let mut forged = signed.clone();
+        forged.signatures[0] = Keypair::new().sign_message(&forged.message_data());
+        let mut unsigned = signed.clone();
+        unsigned.signatures.clear();
+        let mut malformed = signed;
+        malformed.message.instructions[1].program_id_index = 99;
+        for tx in [forged, unsigned, malformed] {
+            assert_eq!(AdmissionPriority::from_transaction(&tx, &[tip_account]), AdmissionPriority::default());
+        }
+    }
+}
@@ -0,0 +1,837 @@
+//! The HTTP API: shared state, the router and its handlers, and the
+//! shutdown drain.
+
+use axum::{
+    routing::{post, get},
+    Router,
+    http::{header, Request, StatusCode},
+    Json,
+    extract::{MatchedPath, State},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    body::Body,
+};
+use std::sync::Arc;
+use tower_http::cors::{CorsLayer, Any};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tower::ServiceBuilder;
+use tracing::{info, error, warn};
+
+use crate::{
+    admission_queue, decoders, errors, health, logging, metrics, models, rate_limiter, shutdown,
+    transaction_display_service, transaction_inspector, validation,
+};
+
+use transaction_display_service::TransactionDisplayService;
+use models::{TransactionRequest, TransactionResponse, ErrorResponse, DisplayedTransaction, TransactionQuery, TransactionPage, DecodeInstructionRequest, DecodeTransactionRequest};
+use rate_limiter::RateLimiter;
+use admission_queue::{AdmissionPriority, AdmissionQueue};
+use metrics::Metrics;
+use health::HealthChecker;
+use shutdown::Shutdown;
+use validation::{TipStage, ValidationError, ValidationPipeline};
+use serde_json::Value;
+use serde_json::json;
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+use decoders::{DecoderRegistry, InstructionView};
+use transaction_inspector::TransactionBreakdown;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Every route, with request tracking, CORS and request-id/tracing layers.
+pub fn router(state: Arc<AppState>) -> Router {
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
+        .route("/stats", get(get_stats))
+        .route("/sendTransaction", post(send_transaction))
+        .route("/transactions", get(get_transactions))
+        .route("/transactions/by-signature/:signature", get(get_transaction_by_signature))
+        .route("/transactions/:id", get(get_transaction_by_id))
+        .route("/rpc", post(json_rpc_handler))
+        .route("/decode", post(decode_transaction))
+        .route("/idl/decode", post(decode_instruction))
+        .route("/metrics", get(get_metrics))
+        .route_layer(middleware::from_fn_with_state(state.clone(), track_requests))
+        .layer(cors)
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(logging::request_span)
+                        .on_response(DefaultOnResponse::new().level(tracing::Level::INFO)),
+                )
+                .layer(PropagateRequestIdLayer::x_request_id()),
+        )
+        .with_state(state.clone())
+}
+/// Waits for the listeners to finish their open requests, up to `deadline`,
+/// then flushes the transaction store and logs what was left behind. Returns
+/// whether every connection finished in time.
+pub async fn drain(
+    state: &AppState,
+    tcp_server: tokio::task::JoinHandle<()>,
+    unix_server: Option<tokio::task::JoinHandle<()>>,
+    deadline: Duration,
+) -> bool {
+    let started = std::time::Instant::now();
+    info!(
+        "Stopped accepting connections; waiting up to {}s for {} in-flight transaction(s)",
+        deadline.as_secs(),
+        state.metrics.in_flight()
+    );
+    let servers = async {
+        let _ = tcp_server.await;
+        if let Some(unix_server) = unix_server {
+            let _ = unix_server.await;
+        }
+    };
+    let drained = tokio::time::timeout(deadline, servers).await.is_ok();
+    let abandoned = state.metrics.in_flight();
+    if !drained {
+        warn!("Shutdown deadline reached; abandoning {} in-flight transaction(s)", abandoned);
+    }
+
+    let service = state.transaction_service.clone();
+    let flushed = match tokio::task::spawn_blocking(move || service.flush_store()).await {
+        Ok(Ok(())) => state.transaction_service.store_stats().await.map_err(|e| e.to_string()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(e) => Err(format!("flush task failed: {}", e)),
+    };
+    let store = match flushed {
+        Ok(stats) => {
+            if stats.backend == "memory" && stats.transactions > 0 {
+                warn!(
+                    "Discarding {} transaction(s) held in memory; set storage.backend = \"sqlite\" to keep history across restarts",
+                    stats.transactions
+                );
+            }
+            format!("{} ({} transactions)", stats.backend, stats.transactions)
+        }
+        Err(e) => {
+            error!("Failed to flush or read the transaction store: {}", e);
+            "flush failed".to_string()
+        }
+    };
+    info!(
+        "Shutdown complete in {}ms: drained={}, abandoned_in_flight={}, store={}",
+        started.elapsed().as_millis(),
+        drained,
+        abandoned,
+        store
+    );
+    drained
+}
+#[derive(Clone)]
+pub struct AppState {
+    pub transaction_service: Arc<TransactionDisplayService>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub admission_queue: Option<Arc<AdmissionQueue>>,
+    pub metrics: Arc<Metrics>,
+    pub tip_pubkeys: Vec<Pubkey>,
+    /// The validation pipeline's tip stage, reused by `/decode`.
+    pub tip_stage: TipStage,
+    pub validation: Arc<ValidationPipeline>,
+    pub decoders: Arc<DecoderRegistry>,
+    pub health: Arc<HealthChecker>,
+    pub shutdown: Shutdown,
+    pub started_at: std::time::Instant,
+}
+async fn health_check() -> StatusCode {
+    StatusCode::OK
+}
+/// The process is up and serving requests; says nothing about upstream RPC.
+async fn health_live(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(json!({
+        "status": "live",
+        "uptime_secs": state.started_at.elapsed().as_secs(),
+    }))
+}
+/// 200 when transactions can be landed, 503 with the failing checks otherwise
+/// or while shutting down.
+async fn health_ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<health::ReadinessReport>) {
+    let queue = state.admission_queue.as_ref().map(|q| (q.depth(), q.max_depth()));
+    let mut report = state
+        .health
+        .readiness(state.transaction_service.store_stats().await, queue)
+        .await;
+    if state.shutdown.is_triggered() {
+        report.ready = false;
+        report.reasons.push("shutting down".to_string());
+    }
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
+}
+async fn get_stats(State(state): State<Arc<AppState>>) -> Json<Value> {
+    let queue_depth = state.admission_queue.as_ref().map(|q| q.depth()).unwrap_or(0);
+    let store_stats = match state.transaction_service.store_stats().await {
+        Ok(stats) => Some(stats),
+        Err(e) => {
+            error!("Failed to read store stats: {}", e);
+            None
+        }
+    };
+    Json(state.metrics.snapshot(queue_depth, store_stats.as_ref()))
+}
+async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let queue_depth = state.admission_queue.as_ref().map(|q| q.depth()).unwrap_or(0);
+    let store_stats = match state.transaction_service.store_stats().await {
+        Ok(stats) => Some(stats),
+        Err(e) => {
+            error!("Failed to read store stats: {}", e);
+            None
+        }
+    };
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render_prometheus(queue_depth, store_stats.as_ref()),
+    )
+}
+/// Counts every routed request by its matched route, method and response status.
+async fn track_requests(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let method = request.method().to_string();
+    let response = next.run(request).await;
+    state.metrics.record_request(&route, &method, response.status().as_u16());
+    response
+}
+
+/// Applies the rate limit. With the admission queue enabled, requests that find
+/// the limit reached or others already waiting queue up, ordered by the tip and
+/// compute unit price they pay.
+async fn check_admission(state: &AppState, encoded_tx: Option<&str>) -> Result<(), String> {
+    let Some(queue) = &state.admission_queue else {
+        if state.rate_limiter.check_rate_limit().await {
+            return Ok(());
+        }
+        state.metrics.record_rejection("rate_limited");
+        return Err("Rate limit exceeded".to_string());
+    };
+    // Decoding and verifying signatures is CPU work on untrusted bytes, so it
+    // only runs for requests that queue, and off the runtime.
+    let priority = async {
+        let Some(encoded) = encoded_tx else {
+            return AdmissionPriority::default();
+        };
+        let encoded = encoded.to_string();
+        let tip_pubkeys = state.tip_pubkeys.clone();
+        tokio::task::spawn_blocking(move || AdmissionPriority::from_encoded(&encoded, &tip_pubkeys))
+            .await
+            .unwrap_or_default()
+    };
+    match queue.admit(priority).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            state.metrics.record_rejection("rate_limited");
+            Err(format!("Rate limit exceeded: {}", e))
+        }
+    }
+}
+
+/// Runs the validation pipeline on a blocking thread, since stages may call RPC.
+async fn validate_transaction(
+    state: &AppState,
+    encoded_tx: &str,
+) -> Result<solana_sdk::transaction::Transaction, ValidationError> {
+    let validation = state.validation.clone();
+    let encoded_tx = encoded_tx.to_string();
+    let span = tracing::Span::current();
+    let result = tokio::task::spawn_blocking(move || span.in_scope(|| validation.run(&encoded_tx)))
+        .await
+        .map_err(|e| ValidationError::Internal(format!("Validation task failed: {}", e)))
+        .and_then(|result| result);
+    if let Err(e) = &result {
+        state.metrics.record_rejection(e.reason());
+    }
+    result
+}
+/// Logs how the relay interprets a validated transaction.
+fn log_transaction(state: &AppState, tx: &solana_sdk::transaction::Transaction) {
+    let breakdown = transaction_inspector::inspect(&tx.clone().into(), &state.decoders, &state.tip_stage);
+    if let Some(payer) = &breakdown.payer {
+        info!("Payer: {}", payer);
+    }
+    info!(
+        "Header: num_required_signatures={}, num_readonly_signed={}, num_readonly_unsigned={}",
+        breakdown.header.num_required_signatures,
+        breakdown.header.num_readonly_signed_accounts,
+        breakdown.header.num_readonly_unsigned_accounts
+    );
+    info!("Recent blockhash: {}", breakdown.recent_blockhash);
+    info!("Num instructions: {}", breakdown.instructions.len());
+    for view in &breakdown.instructions {
+        info!("Instruction #{} program={} accounts={:?}", view.index, view.program_id, view.accounts);
+        info!("  {}", view.summary());
+    }
+}
+async fn json_rpc_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let id = body.get("id").cloned().unwrap_or_else(|| Value::from(1));
+    let method = body.get("method").and_then(|m| m.as_str()).unwrap_or("");
+    info!("JSON-RPC request: method={} id={}", method, id);
+    if method != "sendTransaction" {
+        error!("Validation failed: method not found");
+        let err = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32601, "message": "Method not found"}
+        });
+        return Ok(Json(err));
+    }
+    let encoded = body
+        .get("params")
+        .and_then(|p| p.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|v| v.as_str());
+    if let Err(message) = check_admission(&state, encoded).await {
+        let err = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32098, "message": message}
+        });
+        return Ok(Json(err));
+    }
+    if let Some(e) = encoded {
+        info!("Received sendTransaction {}", logging::RedactedTransaction(e));
+    }
+    let Some(encoded_tx) = encoded else {
+        error!("Validation failed: missing base64 transaction in params");
+        let err = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32602, "message": "Invalid params: missing base64 transaction"}
+        });
+        return Ok(Json(err));
+    };
+    let tx = match validate_transaction(&state, encoded_tx).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            let mut error = json!({"code": e.code(), "message": e.to_string()});
+            if let Some(details) = e.details() {
+                error["data"] = details;
+            }
+            let err = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": error
+            });
+            return Ok(Json(err));
+        }
+    };
+    log_transaction(&state, &tx);
+    let signature = tx
+        .signatures
+        .first()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+        info!("Validation success. Extracted signature: {}", signature);
+    
+    // Create TransactionRequest to call the transaction service
+    let transaction_request = TransactionRequest {
+        from_address: "".to_string(), // Derived from the transaction by the service
+        to_address: "".to_string(),   // Derived from the transaction by the service
+        amount: 0.0,                  // Derived from the transaction by the service
+        memo: None,
+        transaction_data: Some(encoded_tx.to_string()),
+        signature: None,
+    };
+    
+    // Call the transaction service to send and confirm the transaction
+    match state.transaction_service.send_and_display_transaction(&transaction_request).await {
+        Ok(response) => {
+            let resp = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": response.signature.unwrap_or(signature)
+            });
+            Ok(Json(resp))
+        }
+        Err(e) => {
+            error!("Transaction service error: {:?}", e);
+            let err = json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32000, "message": format!("Transaction service failed: {}", e)}
+            });
+            Ok(Json(err))
+        }
+    }
+}
+async fn decode_transaction(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DecodeTransactionRequest>,
+) -> Result<Json<TransactionBreakdown>, (StatusCode, Json<ErrorResponse>)> {
+    let tx = transaction_inspector::decode_versioned(&request.transaction, &request.encoding).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid transaction".to_string(),
+                message: e.to_string(),
+                code: None,
+                details: None,
+            })
+        )
+    })?;
+    Ok(Json(transaction_inspector::inspect(&tx, &state.decoders, &state.tip_stage)))
+}
+async fn decode_instruction(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DecodeInstructionRequest>,
+) -> Result<Json<InstructionView>, (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid decode request".to_string(),
+                message,
+                code: None,
+                details: None,
+            })
+        )
+    };
+    let program_id = Pubkey::from_str(&request.program_id)
+        .map_err(|e| bad_request(format!("Invalid program id: {}", e)))?;
+    let accounts = request
+        .accounts
+        .iter()
+        .map(|a| Pubkey::from_str(a).map_err(|e| bad_request(format!("Invalid account {}: {}", a, e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = match request.encoding.as_str() {
+        "base64" => base64::engine::general_purpose::STANDARD.decode(&request.data).map_err(|e| e.to_string()),
+        "base58" => solana_sdk::bs58::decode(&request.data).into_vec().map_err(|e| e.to_string()),
+        "hex" => hex::decode(&request.data).map_err(|e| e.to_string()),
+        other => Err(format!("unsupported encoding {}", other)),
+    }
+    .map_err(|e| bad_request(format!("Invalid instruction data: {}", e)))?;
+    Ok(Json(state.decoders.decode(0, &program_id, &data, &accounts)))
+}
+async fn send_transaction(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TransactionRequest>,
+) -> Result<Json<TransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    // Check rate limit
+    if let Err(message) = check_admission(&state, request.transaction_data.as_deref()).await {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ErrorResponse {
+                error: message,
+                message: "Too many requests per second".to_string(),
+                code: None,
+                details: None,
+            })
+        ));
+    }
+    let Some(encoded_tx) = request.transaction_data.as_deref() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Validation failed".to_string(),
+                message: "No transaction data provided".to_string(),
+                code: None,
+                details: None,
+            })
+        ));
+    };
+    info!("Received /sendTransaction {}", logging::RedactedTransaction(encoded_tx));
+    match validate_transaction(&state, encoded_tx).await {
+        Ok(tx) => log_transaction(&state, &tx),
+        Err(e) => {
+            return Err((
+                e.status_code(),
+                Json(ErrorResponse {
+                    error: "Validation failed".to_string(),
+                    message: e.to_string(),
+                    code: Some(e.reason().to_string()),
+                    details: e.details(),
+                })
+            ));
+        }
+    }
+    match state.transaction_service.send_and_display_transaction(&request).await {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            error!("Transaction send error: {:?}", e);
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Transaction send failed".to_string(),
+                    message: e.to_string(),
+                    code: None,
+                    details: None,
+                })
+            ))
+        }
+    }
+}
+async fn get_transactions(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(query): axum::extract::Query<TransactionQuery>,
+) -> Result<Json<TransactionPage>, (StatusCode, Json<ErrorResponse>)> {
+    match state.transaction_service.query_transactions(&query).await {
+        Ok(page) => Ok(Json(page)),
+        Err(e @ errors::ServiceError::InvalidQuery(_)) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid query".to_string(),
+                message: e.to_string(),
+                code: None,
+                details: None,
+            })
+        )),
+        Err(e) => {
+            error!("Failed to get transactions: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "Failed to retrieve transactions".to_string(),
+                    message: e.to_string(),
+                    code: None,
+                    details: None,
+                })
+            ))
+        }
+    }
+}
+async fn get_transaction_by_id(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<DisplayedTransaction>, (StatusCode, Json<ErrorResponse>)> {
+    let result = match state.transaction_service.get_transaction_by_id(&id).await {
+        Ok(transaction) => Ok(transaction),
+        // Fall back to the signature index so callers can use either identifier
+        Err(errors::ServiceError::NotFound(_)) => state.transaction_service.get_transaction_by_signature(&id).await,
+        Err(e) => Err(e),
+    };
+    transaction_lookup_response(&id, result)
+}
+async fn get_transaction_by_signature(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(signature): axum::extract::Path<String>,
+) -> Result<Json<DisplayedTransaction>, (StatusCode, Json<ErrorResponse>)> {
+    let result = state.transaction_service.get_transaction_by_signature(&signature).await;
+    transaction_lookup_response(&signature, result)
+}
+fn transaction_lookup_response(
+    key: &str,
+    result: Result<DisplayedTransaction, errors::ServiceError>,
+) -> Result<Json<DisplayedTransaction>, (StatusCode, Json<ErrorResponse>)> {
+    match result {
+        Ok(transaction) => Ok(Json(transaction)),
+        Err(e @ errors::ServiceError::NotFound(_)) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Transaction not found".to_string(),
+                message: e.to_string(),
+                code: None,
+                details: None,
+            })
+        )),
+        Err(e) => {
+            error!("Failed to get transaction {}: {:?}", key, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "Failed to retrieve transaction".to_string(),
+                    message: e.to_string(),
+                    code: None,
+                    details: None,
+                })
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tower::Service;
+    use crate::compute_budget_policy::ComputeBudgetPolicy;
+    use crate::health::HealthConfig;
+    use crate::rpc_pool::RpcPool;
+    use crate::transaction_store::{self, testing::InterceptingStore};
+    use crate::validation::{DecodeStage, SignatureStage, SizeStage};
+
+    /// App state over `store`, with an unreachable RPC endpoint and no blockhash
+    /// or balance checks, so only stored data and local validation are exercised.
+    fn test_state(store: Arc<dyn transaction_store::TransactionStore>) -> Arc<AppState> {
+        let metrics = Arc::new(Metrics::new());
+        let rpc_pool = Arc::new(RpcPool::new(&["http://127.0.0.1:1".to_string()], metrics.clone()));
+        let tip_pubkeys = vec![Pubkey::new_unique()];
+        let tip_stage = TipStage {
+            tip_pubkeys: tip_pubkeys.clone(),
+            min_tip_lamports: 1_000,
+            require_last: false,
+            compute_budget_policy: ComputeBudgetPolicy::default(),
+        };
+        let validation = Arc::new(ValidationPipeline::new(vec![
+            Box::new(DecodeStage),
+            Box::new(SizeStage { max_bytes: solana_sdk::packet::PACKET_DATA_SIZE }),
+            Box::new(SignatureStage { verify: false }),
+            Box::new(tip_stage.clone()),
+        ]));
+        let decoders = Arc::new(DecoderRegistry::with_builtin());
+        let transaction_service = Arc::new(
+            TransactionDisplayService::new(store, tip_pubkeys.clone(), decoders.clone(), rpc_pool.clone(), metrics.clone())
+                .unwrap(),
+        );
+        Arc::new(AppState {
+            transaction_service,
+            rate_limiter: Arc::new(RateLimiter::new(1_000)),
+            admission_queue: None,
+            metrics,
+            tip_pubkeys,
+            tip_stage,
+            validation,
+            decoders,
+            health: Arc::new(HealthChecker::new(rpc_pool, HealthConfig::default())),
+            shutdown: Shutdown::new(),
+            started_at: std::time::Instant::now(),
+        })
+    }
+
+    async fn send(app: &mut Router, request: Request<Body>) -> (StatusCode, axum::http::HeaderMap, Value) {
+        let response = app.call(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (parts.status, parts.headers, value)
+    }
+
+    async fn get(app: &mut Router, uri: &str) -> (StatusCode, Value) {
+        let (status, _, body) = send(app, Request::get(uri).body(Body::empty()).unwrap()).await;
+        (status, body)
+    }
+
+    fn stored(id: &str, signature: &str) -> DisplayedTransaction {
+        DisplayedTransaction {
+            id: id.to_string(),
+            transaction_id: id.to_string(),
+            from_address: "payer".to_string(),
+            to_address: "recipient".to_string(),
+            amount: 0.0,
+            memo: None,
+            status: "confirmed".to_string(),
+            timestamp: Utc::now(),
+            signature: Some(signature.to_string()),
+            block_time: None,
+            transaction_data: None,
+            tip_account: None,
+            tip: None,
+            tip_lamports: 0,
+            transfers: Vec::new(),
+            total_lamports: 0,
+            token_transfers: Vec::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn transactions_are_found_by_id_or_signature() {
+        for backend in ["memory", "sqlite"] {
+            let store = transaction_store::open_store(backend, ":memory:").unwrap();
+            store.insert(stored("tx-1", "sig-1")).unwrap();
+            let mut app = router(test_state(store));
+
+            let (status, body) = get(&mut app, "/transactions/tx-1").await;
+            assert_eq!((status, &body["id"]), (StatusCode::OK, &json!("tx-1")), "{}", backend);
+            let (status, body) = get(&mut app, "/transactions/by-signature/sig-1").await;
+            assert_eq!((status, &body["id"]), (StatusCode::OK, &json!("tx-1")), "{}", backend);
+            // An unknown id falls back to the signature index.
+            let (status, body) = get(&mut app, "/transactions/sig-1").await;
+            assert_eq!((status, &body["id"]), (StatusCode::OK, &json!("tx-1")), "{}", backend);
+
+            for uri in ["/transactions/missing", "/transactions/by-signature/missing"] {
+                let (status, body) = get(&mut app, uri).await;
+                assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", backend, uri);
+                assert_eq!(body["error"], "Transaction not found");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn storage_errors_are_not_reported_as_not_found() {
+        // Every call fails, so errors other than "not found" must not be masked.
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let broken = InterceptingStore::new(transaction_store::open_store("memory", "").unwrap(), move |method| {
+            recorded.lock().unwrap().push(method.to_string());
+            Some(errors::ServiceError::Storage("disk on fire".to_string()))
+        });
+        let mut app = router(test_state(Arc::new(broken)));
+        let (status, body) = get(&mut app, "/transactions/tx-1").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "Failed to retrieve transaction");
+        // The signature fallback only runs when the id is not found.
+        assert_eq!(*calls.lock().unwrap(), ["get"]);
+    }
+
+    async fn post_transaction(app: &mut Router, transaction: &solana_sdk::transaction::Transaction) -> (StatusCode, Value) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap());
+        let body = json!({
+            "from_address": "payer",
+            "to_address": "recipient",
+            "amount": 0.0,
+            "memo": null,
+            "transaction_data": encoded,
+            "signature": null,
+        });
+        let request = Request::post("/sendTransaction")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let (status, _, body) = send(app, request).await;
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn send_transaction_reports_tip_rejections() {
+        use solana_sdk::message::Message;
+        use solana_sdk::system_instruction;
+        use solana_sdk::transaction::Transaction;
+
+        let state = test_state(transaction_store::open_store("memory", "").unwrap());
+        let tip = state.tip_pubkeys[0];
+        let mut app = router(state);
+        let payer = Pubkey::new_unique();
+
+        let untipped = system_instruction::transfer(&payer, &Pubkey::new_unique(), 5_000);
+        let (status, body) =
+            post_transaction(&mut app, &Transaction::new_unsigned(Message::new(&[untipped], Some(&payer)))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(body["code"], "tip_missing");
+        assert_eq!(body["details"], json!({"required_lamports": 1_000, "found_lamports": 0}));
+
+        // The tip comes from an account that does not sign the transaction.
+        let funder = Pubkey::new_unique();
+        let mut unsigned_tip = system_instruction::transfer(&funder, &tip, 1_000);
+        unsigned_tip.accounts[0].is_signer = false;
+        let (status, body) =
+            post_transaction(&mut app, &Transaction::new_unsigned(Message::new(&[unsigned_tip], Some(&payer)))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "tip_payer_not_signer");
+        assert_eq!(
+            body["message"],
+            format!("Tip transfer from {} is not signed by its funding authority", funder)
+        );
+    }
+
+    #[tokio::test]
+    async fn readiness_fails_with_reasons_when_upstream_is_down() {
+        let mut app = router(test_state(transaction_store::open_store("memory", "").unwrap()));
+        let (status, body) = get(&mut app, "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["reasons"], json!(["no healthy RPC endpoint", "no fresh blockhash"]));
+        assert_eq!(body["rpc"][0]["reachable"], false);
+        assert_eq!(body["storage"]["ok"], true);
+
+        let (status, _) = get(&mut app, "/health/live").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unknown_rpc_methods_do_not_use_rate_limit_slots() {
+        let mut state = test_state(transaction_store::open_store("memory", "").unwrap());
+        let rate_limiter = Arc::new(RateLimiter::new(1));
+        Arc::get_mut(&mut state).unwrap().rate_limiter = rate_limiter.clone();
+        let mut app = router(state);
+        for _ in 0..3 {
+            let request = Request::post("/rpc")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"jsonrpc": "2.0", "id": 1, "method": "getHealth"}).to_string()))
+                .unwrap();
+            let (_, _, body) = send(&mut app, request).await;
+            assert_eq!(body["error"]["code"], -32601);
+        }
+        assert!(rate_limiter.try_acquire());
+    }
+
+    /// Records the fields of every `request` span.
+    #[derive(Clone, Default)]
+    struct RequestSpans(Arc<std::sync::Mutex<Vec<std::collections::HashMap<String, String>>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for RequestSpans {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct Fields<'a>(&'a mut std::collections::HashMap<String, String>);
+            impl tracing::field::Visit for Fields<'_> {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                    self.0.insert(field.name().to_string(), format!("{:?}", value));
+                }
+            }
+            if attrs.metadata().name() == "request" {
+                let mut fields = std::collections::HashMap::new();
+                attrs.record(&mut Fields(&mut fields));
+                self.0.lock().unwrap().push(fields);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn request_id_is_echoed_and_tags_the_request_span() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let spans = RequestSpans::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+        let mut app = router(test_state(transaction_store::open_store("memory", "").unwrap()));
+
+        let request = Request::get("/health").header("x-request-id", "req-42").body(Body::empty()).unwrap();
+        let (_, headers, _) = send(&mut app, request).await;
+        assert_eq!(headers["x-request-id"], "req-42");
+
+        // Without the header an id is generated and used the same way.
+        let (_, headers, _) = send(&mut app, Request::get("/health").body(Body::empty()).unwrap()).await;
+        let generated = headers["x-request-id"].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&generated).is_ok());
+
+        let spans = spans.0.lock().unwrap();
+        let ids: Vec<&str> = spans.iter().map(|fields| fields["request_id"].as_str()).collect();
+        assert_eq!(ids, vec!["req-42", generated.as_str()]);
+        assert_eq!(spans[0]["path"], "/health");
+    }
+
+    #[tokio::test]
+    async fn drain_flushes_the_store_and_reports_the_deadline() {
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = count.clone();
+        let store = InterceptingStore::new(transaction_store::open_store("memory", "").unwrap(), move |method| {
+            if method == "flush" {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+            None
+        });
+        let state = test_state(Arc::new(store));
+        let flushes = || count.load(std::sync::atomic::Ordering::SeqCst);
+
+        let finished = tokio::spawn(async {});
+        assert!(drain(&state, finished, None, Duration::from_secs(5)).await);
+        assert_eq!(flushes(), 1);
+
+        // A server still running at the deadline is abandoned, but the store
+        // is flushed all the same.
+        let stuck = tokio::spawn(std::future::pending::<()>());
+        let finished = tokio::spawn(async {});
+        assert!(!drain(&state, finished, Some(stuck), Duration::from_millis(50)).await);
+        assert_eq!(flushes(), 2);
+    }
+}
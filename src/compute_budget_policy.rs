@@ -0,0 +1,123 @@
+use crate::decoders::compute_budget::ComputeBudgetSettings;
+
+use thiserror::Error;
+
+/// Limits applied to the Compute Budget instructions of relayed transactions.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeBudgetPolicy {
+    /// Minimum `SetComputeUnitPrice` in micro-lamports. A missing instruction counts as 0.
+    pub min_unit_price: Option<u64>,
+    /// Maximum `SetComputeUnitLimit`. Transactions without one use the runtime default.
+    pub max_unit_limit: Option<u32>,
+    pub reject_duplicates: bool,
+    /// Count the priority fee (unit price × unit limit) toward the minimum tip.
+    pub priority_fee_counts_toward_tip: bool,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ComputeBudgetViolation {
+    #[error("Compute unit price too low: required >= {required} micro-lamports, found {found}")]
+    UnitPriceTooLow { required: u64, found: u64 },
+
+    #[error("Compute unit limit too high: maximum {max}, requested {requested}")]
+    UnitLimitTooHigh { max: u32, requested: u32 },
+
+    #[error("Duplicate compute budget instruction: {0}")]
+    DuplicateInstruction(String),
+}
+
+impl ComputeBudgetViolation {
+    /// JSON-RPC error code returned for the violation.
+    pub fn code(&self) -> i64 {
+        match self {
+            ComputeBudgetViolation::UnitPriceTooLow { .. } => -32002,
+            ComputeBudgetViolation::UnitLimitTooHigh { .. } => -32003,
+            ComputeBudgetViolation::DuplicateInstruction(_) => -32004,
+        }
+    }
+}
+
+impl ComputeBudgetPolicy {
+    pub fn check(&self, settings: &ComputeBudgetSettings) -> Result<(), ComputeBudgetViolation> {
+        if self.reject_duplicates {
+            if let Some(name) = settings.duplicate_instructions.first() {
+                return Err(ComputeBudgetViolation::DuplicateInstruction(name.clone()));
+            }
+        }
+        if let Some(max) = self.max_unit_limit {
+            let requested = settings.effective_unit_limit();
+            if requested > max {
+                return Err(ComputeBudgetViolation::UnitLimitTooHigh { max, requested });
+            }
+        }
+        if let Some(required) = self.min_unit_price {
+            let found = settings.unit_price_micro_lamports.unwrap_or(0);
+            if found < required {
+                return Err(ComputeBudgetViolation::UnitPriceTooLow { required, found });
+            }
+        }
+        Ok(())
+    }
+
+    /// Lamports credited toward the tip requirement on top of the tip transfer.
+    pub fn tip_credit(&self, settings: &ComputeBudgetSettings) -> u64 {
+        if self.priority_fee_counts_toward_tip {
+            settings.priority_fee_lamports()
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(unit_limit: u32, price: u64) -> ComputeBudgetSettings {
+        ComputeBudgetSettings {
+            unit_limit: Some(unit_limit),
+            unit_price_micro_lamports: Some(price),
+            other_instructions: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tip_credit_only_when_enabled() {
+        let budget = settings(200_000, 5_000);
+        assert_eq!(ComputeBudgetPolicy::default().tip_credit(&budget), 0);
+        let policy = ComputeBudgetPolicy { priority_fee_counts_toward_tip: true, ..Default::default() };
+        assert_eq!(policy.tip_credit(&budget), 1_000);
+        assert_eq!(policy.tip_credit(&settings(1_400_000, u64::MAX)), u64::MAX);
+    }
+
+    #[test]
+    fn check_applies_limits_in_order() {
+        let policy = ComputeBudgetPolicy {
+            min_unit_price: Some(100),
+            max_unit_limit: Some(300_000),
+            reject_duplicates: true,
+            priority_fee_counts_toward_tip: false,
+        };
+        assert_eq!(policy.check(&settings(300_000, 100)), Ok(()));
+        assert_eq!(
+            policy.check(&settings(300_001, 0)),
+            Err(ComputeBudgetViolation::UnitLimitTooHigh { max: 300_000, requested: 300_001 })
+        );
+        assert_eq!(
+            policy.check(&settings(1, 99)),
+            Err(ComputeBudgetViolation::UnitPriceTooLow { required: 100, found: 99 })
+        );
+        let unpriced = ComputeBudgetSettings { other_instructions: 1, ..Default::default() };
+        assert_eq!(
+            policy.check(&unpriced),
+            Err(ComputeBudgetViolation::UnitPriceTooLow { required: 100, found: 0 })
+        );
+        let mut duplicated = settings(1, 100);
+        duplicated.duplicate_instructions.push("SetComputeUnitPrice".to_string());
+        assert_eq!(
+            policy.check(&duplicated),
+            Err(ComputeBudgetViolation::DuplicateInstruction("SetComputeUnitPrice".to_string()))
+        );
+    }
+}
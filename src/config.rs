@@ -0,0 +1,706 @@
+use crate::errors::ServiceError;
+use crate::rpc_endpoints::RPC_ENDPOINTS;
+use crate::tip_accounts::{MIN_TIP, TIP_ACCOUNTS};
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::sol_to_lamports;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Command line flags. Each one overrides the matching config file and
+/// environment setting.
+#[derive(Debug, Parser)]
+#[command(version, about = "Solana transaction relay and display service")]
+pub struct Cli {
+    /// TOML configuration file.
+    #[arg(short, long, env = "CONFIG_FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Print the resolved configuration as TOML and exit.
+    #[arg(long)]
+    pub print_config: bool,
+
+    /// IP address to listen on.
+    #[arg(long)]
+    pub bind: Option<String>,
+
+    /// Port to listen on.
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Fail instead of falling back to another port when the configured one is
+    /// taken. `--strict-bind=false` turns off `strict_bind` set by the file or environment.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub strict_bind: Option<bool>,
+
+    /// Also listen on this Unix domain socket.
+    #[arg(long)]
+    pub unix_socket: Option<String>,
+
+    /// PEM certificate chain; enables TLS together with `--tls-key`.
+    #[arg(long)]
+    pub tls_cert: Option<String>,
+
+    /// PEM private key.
+    #[arg(long)]
+    pub tls_key: Option<String>,
+
+    /// PEM CA bundle used to verify client certificates (mutual TLS).
+    #[arg(long)]
+    pub tls_client_ca: Option<String>,
+
+    /// Upstream RPC endpoint; repeat or comma-separate for several.
+    #[arg(long = "rpc-endpoint", value_delimiter = ',')]
+    pub rpc_endpoints: Vec<String>,
+
+    /// Tip account; repeat or comma-separate for several.
+    #[arg(long = "tip-account", value_delimiter = ',')]
+    pub tip_accounts: Vec<String>,
+
+    /// Smallest accepted tip, in lamports.
+    #[arg(long)]
+    pub min_tip_lamports: Option<u64>,
+
+    /// Transactions per second before requests are rate limited or queued.
+    #[arg(long)]
+    pub tps_limit: Option<u32>,
+
+    /// `memory` or `sqlite`.
+    #[arg(long)]
+    pub storage_backend: Option<String>,
+
+    /// SQLite database file.
+    #[arg(long)]
+    pub storage_path: Option<String>,
+
+    /// `text` or `json`.
+    #[arg(long)]
+    pub log_format: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// IPv4 or IPv6 address, e.g. `0.0.0.0`, `127.0.0.1`, `::` or `::1`.
+    pub bind: String,
+    pub port: u16,
+    /// Fail when `port` is taken instead of trying 3000-3010 and then an ephemeral port.
+    pub strict_bind: bool,
+    /// Also serve on this Unix domain socket.
+    pub unix_socket: Option<String>,
+    /// On SIGTERM/SIGINT, how long to wait for in-flight requests before exiting.
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0".to_string(),
+            port: 3000,
+            strict_bind: false,
+            unix_socket: None,
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    /// Enables mutual TLS: client certificates must chain to this CA bundle.
+    pub client_ca_path: Option<String>,
+    /// Reject clients without a certificate; when false they may connect anonymously.
+    pub require_client_cert: bool,
+    /// How often to check the certificate, key and CA files for changes.
+    pub reload_secs: u64,
+    /// Client certificate SHA-256 fingerprint (hex) to customer name.
+    pub clients_by_fingerprint: BTreeMap<String, String>,
+    /// Client certificate subject CN to customer name, used when the
+    /// fingerprint is not listed.
+    pub clients_by_cn: BTreeMap<String, String>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            cert_path: None,
+            key_path: None,
+            client_ca_path: None,
+            require_client_cert: true,
+            reload_secs: 30,
+            clients_by_fingerprint: BTreeMap::new(),
+            clients_by_cn: BTreeMap::new(),
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.cert_path.is_some() || self.key_path.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    pub endpoints: Vec<String>,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: RPC_ENDPOINTS.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TipConfig {
+    pub accounts: Vec<String>,
+    pub min_lamports: u64,
+    pub require_last: bool,
+}
+
+impl Default for TipConfig {
+    fn default() -> Self {
+        Self {
+            accounts: TIP_ACCOUNTS.iter().map(|a| a.to_string()).collect(),
+            min_lamports: sol_to_lamports(MIN_TIP),
+            require_last: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub tps: u32,
+    pub max_transaction_bytes: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            tps: 1,
+            max_transaction_bytes: solana_sdk::packet::PACKET_DATA_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdmissionQueueConfig {
+    pub enabled: bool,
+    pub depth: usize,
+    pub max_wait_ms: u64,
+}
+
+impl Default for AdmissionQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            depth: 100,
+            max_wait_ms: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidationConfig {
+    pub verify_signatures: bool,
+    pub blockhash_check: bool,
+    pub fee_payer_balance_check: bool,
+    pub fee_payer_balance_cache_ms: u64,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            verify_signatures: true,
+            blockhash_check: false,
+            fee_payer_balance_check: false,
+            fee_payer_balance_cache_ms: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComputeBudgetConfig {
+    pub min_unit_price: Option<u64>,
+    pub max_unit_limit: Option<u32>,
+    pub reject_duplicates: bool,
+    pub priority_fee_counts_toward_tip: bool,
+}
+
+impl Default for ComputeBudgetConfig {
+    fn default() -> Self {
+        Self {
+            min_unit_price: None,
+            max_unit_limit: None,
+            reject_duplicates: true,
+            priority_fee_counts_toward_tip: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessPolicySettings {
+    pub file: Option<String>,
+    pub audit_log: Option<String>,
+    pub reload_secs: u64,
+}
+
+impl Default for AccessPolicySettings {
+    fn default() -> Self {
+        Self {
+            file: None,
+            audit_log: None,
+            reload_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// `memory` or `sqlite`.
+    pub backend: String,
+    pub path: String,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: "memory".to_string(),
+            path: "transactions.db".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    pub max_age_secs: Option<u64>,
+    pub max_count: Option<usize>,
+    pub strip_payload_after_mins: Option<u64>,
+    pub archive_path: Option<String>,
+    pub interval_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_secs: None,
+            max_count: None,
+            strip_payload_after_mins: None,
+            archive_path: None,
+            interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdlConfig {
+    pub dir: Option<String>,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthSettings {
+    pub max_slot_lag: u64,
+    pub rpc_timeout_ms: u64,
+    pub max_blockhash_age_secs: u64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            max_slot_lag: 50,
+            rpc_timeout_ms: 2000,
+            max_blockhash_age_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// `text` or `json`.
+    pub format: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: "text".to_string(),
+        }
+    }
+}
+
+/// Service configuration: built-in defaults, then the TOML file, then
+/// environment variables, then command line flags.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub tls: TlsConfig,
+    pub rpc: RpcConfig,
+    pub tips: TipConfig,
+    pub limits: LimitsConfig,
+    pub admission_queue: AdmissionQueueConfig,
+    pub validation: ValidationConfig,
+    pub compute_budget: ComputeBudgetConfig,
+    pub access_policy: AccessPolicySettings,
+    pub storage: StorageConfig,
+    pub retention: RetentionConfig,
+    pub idl: IdlConfig,
+    pub health: HealthSettings,
+    pub logging: LoggingConfig,
+}
+
+fn invalid(message: impl Into<String>) -> ServiceError {
+    ServiceError::InvalidConfig(message.into())
+}
+
+/// Reads a variable; [`Config::load`] passes the process environment, tests a map.
+type EnvLookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Reads and parses `name`, treating unset and empty values as absent.
+fn env_parse<T: FromStr>(env: EnvLookup, name: &str) -> Result<Option<T>, ServiceError>
+where
+    T::Err: std::fmt::Display,
+{
+    match env(name) {
+        Some(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| invalid(format!("{}={:?}: {}", name, value, e))),
+        _ => Ok(None),
+    }
+}
+
+fn env_bool(env: EnvLookup, name: &str) -> Result<Option<bool>, ServiceError> {
+    match env(name) {
+        Some(value) if !value.trim().is_empty() => match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => Ok(Some(true)),
+            "0" | "false" | "no" => Ok(Some(false)),
+            _ => Err(invalid(format!("{}={:?}: expected true or false", name, value))),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Splits a comma-separated list. Like the other readers, a blank value counts
+/// as unset rather than as an empty list.
+fn env_list(env: EnvLookup, name: &str) -> Option<Vec<String>> {
+    env(name).filter(|value| !value.trim().is_empty()).map(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+fn set<T>(target: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *target = value;
+    }
+}
+
+impl Config {
+    /// Resolves the layered configuration and validates it.
+    pub fn load(cli: &Cli) -> Result<Self, ServiceError> {
+        Self::load_with_env(cli, &|name| std::env::var(name).ok())
+    }
+
+    fn load_with_env(cli: &Cli, env: EnvLookup) -> Result<Self, ServiceError> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(env)?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &PathBuf) -> Result<Self, ServiceError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| invalid(format!("Failed to read {}: {}", path.display(), e)))?;
+        toml::from_str(&text).map_err(|e| invalid(format!("Failed to parse {}: {}", path.display(), e)))
+    }
+
+    fn apply_env(&mut self, env: EnvLookup) -> Result<(), ServiceError> {
+        set(&mut self.server.bind, env_parse(env, "BIND_ADDRESS")?);
+        set(&mut self.server.port, env_parse(env, "PORT")?);
+        set(&mut self.server.strict_bind, env_bool(env, "STRICT_BIND")?);
+        set(&mut self.server.unix_socket, env_parse(env, "UNIX_SOCKET")?.map(Some));
+        set(&mut self.server.shutdown_timeout_secs, env_parse(env, "SHUTDOWN_TIMEOUT_SECS")?);
+
+        set(&mut self.tls.cert_path, env_parse(env, "TLS_CERT_PATH")?.map(Some));
+        set(&mut self.tls.key_path, env_parse(env, "TLS_KEY_PATH")?.map(Some));
+        set(&mut self.tls.client_ca_path, env_parse(env, "TLS_CLIENT_CA_PATH")?.map(Some));
+        set(&mut self.tls.require_client_cert, env_bool(env, "TLS_REQUIRE_CLIENT_CERT")?);
+        set(&mut self.tls.reload_secs, env_parse(env, "TLS_RELOAD_SECS")?);
+
+        // SOLANA_RPC_URL is what start.sh exports; RPC_ENDPOINTS takes a list.
+        set(&mut self.rpc.endpoints, env_parse::<String>(env, "SOLANA_RPC_URL")?.map(|url| vec![url]));
+        set(&mut self.rpc.endpoints, env_list(env, "RPC_ENDPOINTS"));
+
+        set(&mut self.tips.accounts, env_list(env, "TIP_ACCOUNTS"));
+        set(&mut self.tips.min_lamports, env_parse(env, "MIN_TIP_LAMPORTS")?);
+        set(&mut self.tips.require_last, env_bool(env, "REQUIRE_TIP_LAST")?);
+
+        set(&mut self.limits.tps, env_parse(env, "TPS_LIMIT")?);
+        set(&mut self.limits.max_transaction_bytes, env_parse(env, "MAX_TRANSACTION_BYTES")?);
+
+        set(&mut self.admission_queue.enabled, env_bool(env, "ADMISSION_QUEUE_ENABLED")?);
+        set(&mut self.admission_queue.depth, env_parse(env, "ADMISSION_QUEUE_DEPTH")?);
+        set(&mut self.admission_queue.max_wait_ms, env_parse(env, "ADMISSION_QUEUE_MAX_WAIT_MS")?);
+
+        set(&mut self.validation.verify_signatures, env_bool(env, "VERIFY_SIGNATURES")?);
+        set(&mut self.validation.blockhash_check, env_bool(env, "BLOCKHASH_CHECK_ENABLED")?);
+        set(&mut self.validation.fee_payer_balance_check, env_bool(env, "FEE_PAYER_BALANCE_CHECK_ENABLED")?);
+        set(&mut self.validation.fee_payer_balance_cache_ms, env_parse(env, "FEE_PAYER_BALANCE_CACHE_MS")?);
+
+        set(&mut self.compute_budget.min_unit_price, env_parse(env, "MIN_COMPUTE_UNIT_PRICE")?.map(Some));
+        set(&mut self.compute_budget.max_unit_limit, env_parse(env, "MAX_COMPUTE_UNIT_LIMIT")?.map(Some));
+        set(&mut self.compute_budget.reject_duplicates, env_bool(env, "REJECT_DUPLICATE_COMPUTE_BUDGET")?);
+        set(
+            &mut self.compute_budget.priority_fee_counts_toward_tip,
+            env_bool(env, "PRIORITY_FEE_COUNTS_TOWARD_TIP")?,
+        );
+
+        set(&mut self.access_policy.file, env_parse(env, "ACCESS_POLICY_FILE")?.map(Some));
+        set(&mut self.access_policy.audit_log, env_parse(env, "ACCESS_POLICY_AUDIT_LOG")?.map(Some));
+        set(&mut self.access_policy.reload_secs, env_parse(env, "ACCESS_POLICY_RELOAD_SECS")?);
+
+        set(&mut self.storage.backend, env_parse(env, "STORAGE_BACKEND")?);
+        set(&mut self.storage.path, env_parse(env, "STORAGE_PATH")?);
+
+        set(&mut self.retention.max_age_secs, env_parse(env, "RETENTION_MAX_AGE_SECS")?.map(Some));
+        set(&mut self.retention.max_count, env_parse(env, "RETENTION_MAX_COUNT")?.map(Some));
+        set(
+            &mut self.retention.strip_payload_after_mins,
+            env_parse(env, "RETENTION_STRIP_PAYLOAD_AFTER_MINS")?.map(Some),
+        );
+        set(&mut self.retention.archive_path, env_parse(env, "RETENTION_ARCHIVE_PATH")?.map(Some));
+        set(&mut self.retention.interval_secs, env_parse(env, "RETENTION_INTERVAL_SECS")?);
+
+        set(&mut self.idl.dir, env_parse(env, "ANCHOR_IDL_DIR")?.map(Some));
+        set(&mut self.idl.files, env_list(env, "ANCHOR_IDL_FILES"));
+
+        set(&mut self.health.max_slot_lag, env_parse(env, "HEALTH_MAX_SLOT_LAG")?);
+        set(&mut self.health.rpc_timeout_ms, env_parse(env, "HEALTH_RPC_TIMEOUT_MS")?);
+        set(&mut self.health.max_blockhash_age_secs, env_parse(env, "HEALTH_MAX_BLOCKHASH_AGE_SECS")?);
+
+        set(&mut self.logging.format, env_parse(env, "LOG_FORMAT")?);
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        set(&mut self.server.bind, cli.bind.clone());
+        set(&mut self.server.port, cli.port);
+        set(&mut self.server.strict_bind, cli.strict_bind);
+        set(&mut self.server.unix_socket, cli.unix_socket.clone().map(Some));
+        set(&mut self.tls.cert_path, cli.tls_cert.clone().map(Some));
+        set(&mut self.tls.key_path, cli.tls_key.clone().map(Some));
+        set(&mut self.tls.client_ca_path, cli.tls_client_ca.clone().map(Some));
+        if !cli.rpc_endpoints.is_empty() {
+            self.rpc.endpoints = cli.rpc_endpoints.clone();
+        }
+        if !cli.tip_accounts.is_empty() {
+            self.tips.accounts = cli.tip_accounts.clone();
+        }
+        set(&mut self.tips.min_lamports, cli.min_tip_lamports);
+        set(&mut self.limits.tps, cli.tps_limit);
+        set(&mut self.storage.backend, cli.storage_backend.clone());
+        set(&mut self.storage.path, cli.storage_path.clone());
+        set(&mut self.logging.format, cli.log_format.clone());
+    }
+
+    /// Rejects settings the service cannot start with, listing every problem at once.
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        let mut problems = Vec::new();
+        if IpAddr::from_str(&self.server.bind).is_err() {
+            problems.push(format!("server.bind: {:?} is not an IP address", self.server.bind));
+        }
+        if self.server.unix_socket.as_deref() == Some("") {
+            problems.push("server.unix_socket: path is empty".to_string());
+        }
+        if cfg!(not(unix)) && self.server.unix_socket.is_some() {
+            problems.push("server.unix_socket: Unix domain sockets are only supported on Unix platforms".to_string());
+        }
+        if self.tls.cert_path.is_some() != self.tls.key_path.is_some() {
+            problems.push("tls: cert_path and key_path must be set together".to_string());
+        }
+        if self.tls.client_ca_path.is_some() && !self.tls.is_enabled() {
+            problems.push("tls.client_ca_path: requires cert_path and key_path".to_string());
+        }
+        if self.rpc.endpoints.is_empty() {
+            problems.push("rpc.endpoints: at least one endpoint is required".to_string());
+        }
+        for endpoint in &self.rpc.endpoints {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                problems.push(format!("rpc.endpoints: {:?} is not an http(s) URL", endpoint));
+            }
+        }
+        if self.tips.accounts.is_empty() {
+            problems.push("tips.accounts: at least one tip account is required".to_string());
+        }
+        for account in &self.tips.accounts {
+            if Pubkey::from_str(account).is_err() {
+                problems.push(format!("tips.accounts: {:?} is not a valid pubkey", account));
+            }
+        }
+        if self.limits.tps == 0 {
+            problems.push("limits.tps: must be at least 1".to_string());
+        }
+        if self.admission_queue.enabled && self.admission_queue.depth == 0 {
+            problems.push("admission_queue.depth: must be at least 1 when enabled".to_string());
+        }
+        if self.retention.interval_secs == 0 {
+            problems.push("retention.interval_secs: must be at least 1".to_string());
+        }
+        if self.health.rpc_timeout_ms == 0 {
+            problems.push("health.rpc_timeout_ms: must be at least 1".to_string());
+        }
+        if !matches!(self.storage.backend.as_str(), "memory" | "sqlite") {
+            problems.push(format!("storage.backend: {:?} is not memory or sqlite", self.storage.backend));
+        }
+        if !matches!(self.logging.format.as_str(), "text" | "json") {
+            problems.push(format!("logging.format: {:?} is not text or json", self.logging.format));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid(problems.join("; ")))
+        }
+    }
+
+    /// Parsed tip accounts; `validate` has already rejected invalid ones.
+    pub fn tip_pubkeys(&self) -> Vec<Pubkey> {
+        self.tips
+            .accounts
+            .iter()
+            .filter_map(|a| Pubkey::from_str(a).ok())
+            .collect()
+    }
+
+    pub fn to_toml(&self) -> Result<String, ServiceError> {
+        toml::to_string_pretty(self).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(config: &Config) -> String {
+        match config.validate() {
+            Err(ServiceError::InvalidConfig(message)) => message,
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        Config::default().validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_zero_intervals() {
+        let mut config = Config::default();
+        config.retention.interval_secs = 0;
+        config.health.rpc_timeout_ms = 0;
+        let message = problems(&config);
+        assert!(message.contains("retention.interval_secs: must be at least 1"), "{}", message);
+        assert!(message.contains("health.rpc_timeout_ms: must be at least 1"), "{}", message);
+    }
+
+    #[test]
+    fn validate_lists_every_problem() {
+        let mut config = Config::default();
+        config.server.bind = "localhost".to_string();
+        config.rpc.endpoints = vec!["ws://example.com".to_string()];
+        config.tips.accounts = vec!["not-a-key".to_string()];
+        config.limits.tps = 0;
+        config.tls.key_path = Some("key.pem".to_string());
+        let message = problems(&config);
+        for field in ["server.bind", "rpc.endpoints", "tips.accounts", "limits.tps", "tls:"] {
+            assert!(message.contains(field), "{} missing from {}", field, message);
+        }
+    }
+
+    fn env_map(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: std::collections::HashMap<String, String> =
+            vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn layers_apply_file_then_env_then_cli() {
+        let path = std::env::temp_dir().join(format!("config-layering-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[server]\nport = 4000\nstrict_bind = true\n[limits]\ntps = 10\n[logging]\nformat = \"json\"\n[retention]\ninterval_secs = 5\n\
+             [rpc]\nendpoints = [\"https://rpc.example.com\"]\n",
+        )
+        .unwrap();
+        let config_arg = path.to_str().unwrap().to_string();
+        // Blank lists, as left by a `.env` line with its value removed, are unset.
+        let env = env_map(&[
+            ("PORT", "5000"),
+            ("TPS_LIMIT", "20"),
+            ("RPC_ENDPOINTS", ""),
+            ("TIP_ACCOUNTS", "  "),
+        ]);
+
+        let cli = Cli::try_parse_from(["service", "--config", &config_arg, "--tps-limit", "30"]).unwrap();
+        let config = Config::load_with_env(&cli, &env);
+
+        let cli = Cli::try_parse_from(["service", "--config", &config_arg, "--strict-bind=false"]).unwrap();
+        let loose = Config::load_with_env(&cli, &env);
+
+        let zero_interval_env = env_map(&[("RETENTION_INTERVAL_SECS", "0")]);
+        let cli = Cli::try_parse_from(["service", "--config", &config_arg]).unwrap();
+        let zero_interval = Config::load_with_env(&cli, &zero_interval_env);
+        std::fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.server.port, 5000, "environment overrides the file");
+        assert_eq!(config.limits.tps, 30, "flags override the environment");
+        assert_eq!(config.logging.format, "json", "the file overrides defaults");
+        assert!(config.server.strict_bind);
+        assert_eq!(config.retention.interval_secs, 5);
+        assert_eq!(config.rpc.endpoints, ["https://rpc.example.com"], "a blank list keeps the file's");
+        assert_eq!(config.tips.accounts, Config::default().tips.accounts);
+        assert_eq!(config.health.rpc_timeout_ms, HealthSettings::default().rpc_timeout_ms);
+        assert!(!loose.unwrap().server.strict_bind, "--strict-bind=false overrides the file");
+        assert!(matches!(zero_interval, Err(ServiceError::InvalidConfig(m)) if m.contains("retention.interval_secs")));
+    }
+
+    #[test]
+    fn strict_bind_flag_accepts_an_optional_value() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["service"], args].concat()).unwrap().strict_bind;
+        assert_eq!(parse(&[]), None);
+        assert_eq!(parse(&["--strict-bind"]), Some(true));
+        assert_eq!(parse(&["--strict-bind=false"]), Some(false));
+        assert_eq!(parse(&["--strict-bind", "--port", "3000"]), Some(true));
+    }
+}
@@ -0,0 +1,59 @@
+use super::{DecodedInstruction, InstructionDecoder};
+
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociatedTokenInstruction {
+    Create,
+    CreateIdempotent,
+    RecoverNested,
+}
+
+impl AssociatedTokenInstruction {
+    /// Empty data is the legacy encoding of `Create`.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        match data.first() {
+            None | Some(0) => Some(Self::Create),
+            Some(1) => Some(Self::CreateIdempotent),
+            Some(2) => Some(Self::RecoverNested),
+            Some(_) => None,
+        }
+    }
+}
+
+pub struct AssociatedTokenDecoder;
+
+impl InstructionDecoder for AssociatedTokenDecoder {
+    fn program_name(&self) -> &str {
+        "spl-associated-token-account"
+    }
+
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<DecodedInstruction> {
+        let instruction = AssociatedTokenInstruction::unpack(data)?;
+        let key = |i: usize| accounts.get(i).map(|k| k.to_string());
+        let data = match instruction {
+            AssociatedTokenInstruction::Create | AssociatedTokenInstruction::CreateIdempotent => json!({
+                "funding_account": key(0),
+                "associated_account": key(1),
+                "wallet": key(2),
+                "mint": key(3),
+                "token_program": key(5),
+            }),
+            AssociatedTokenInstruction::RecoverNested => json!({
+                "nested_account": key(0),
+                "nested_mint": key(1),
+                "destination_account": key(2),
+                "owner_account": key(3),
+                "owner_mint": key(4),
+                "wallet": key(5),
+            }),
+        };
+        Some(DecodedInstruction {
+            name: format!("{:?}", instruction),
+            data,
+        })
+    }
+}
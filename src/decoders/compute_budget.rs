@@ -0,0 +1,151 @@
+use super::system::split_variant;
+use super::{DecodedInstruction, InstructionDecoder};
+
+use serde::Serialize;
+use solana_sdk::borsh1::try_from_slice_unchecked;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::pubkey::Pubkey;
+
+/// Default per-instruction compute unit limit when none is requested.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// Compute budget instructions are borsh encoded (single byte tag).
+pub fn parse(data: &[u8]) -> Option<ComputeBudgetInstruction> {
+    try_from_slice_unchecked::<ComputeBudgetInstruction>(data).ok()
+}
+
+pub struct ComputeBudgetDecoder;
+
+impl InstructionDecoder for ComputeBudgetDecoder {
+    fn program_name(&self) -> &str {
+        "compute-budget"
+    }
+
+    fn decode(&self, data: &[u8], _accounts: &[Pubkey]) -> Option<DecodedInstruction> {
+        let instruction = parse(data)?;
+        let (name, data) = split_variant(serde_json::to_value(&instruction).ok()?);
+        Some(DecodedInstruction { name, data })
+    }
+}
+
+/// Compute budget requested by a transaction's Compute Budget instructions.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ComputeBudgetSettings {
+    pub unit_limit: Option<u32>,
+    pub unit_price_micro_lamports: Option<u64>,
+    pub heap_frame_bytes: Option<u32>,
+    pub loaded_accounts_data_size_limit: Option<u32>,
+    /// Number of Compute Budget program instructions.
+    pub budget_instructions: usize,
+    /// Number of other instructions, used for the default unit limit.
+    pub other_instructions: usize,
+    /// Compute Budget instructions that appear more than once. The runtime
+    /// rejects such transactions with `DuplicateInstruction`.
+    pub duplicate_instructions: Vec<String>,
+}
+
+impl ComputeBudgetSettings {
+    pub fn from_instructions(account_keys: &[Pubkey], instructions: &[CompiledInstruction]) -> Self {
+        let mut settings = Self::default();
+        for ix in instructions {
+            if account_keys.get(ix.program_id_index as usize) != Some(&compute_budget::id()) {
+                settings.other_instructions += 1;
+                continue;
+            }
+            settings.budget_instructions += 1;
+            let (name, duplicate) = match parse(&ix.data) {
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(limit)) => {
+                    ("SetComputeUnitLimit", settings.unit_limit.replace(limit).is_some())
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => {
+                    ("SetComputeUnitPrice", settings.unit_price_micro_lamports.replace(price).is_some())
+                }
+                Some(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                    ("RequestHeapFrame", settings.heap_frame_bytes.replace(bytes).is_some())
+                }
+                Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => (
+                    "SetLoadedAccountsDataSizeLimit",
+                    settings.loaded_accounts_data_size_limit.replace(bytes).is_some(),
+                ),
+                Some(ComputeBudgetInstruction::Unused) | None => continue,
+            };
+            if duplicate && !settings.duplicate_instructions.iter().any(|d| d == name) {
+                settings.duplicate_instructions.push(name.to_string());
+            }
+        }
+        settings
+    }
+
+    /// Requested limit, or the runtime default for the number of instructions.
+    pub fn effective_unit_limit(&self) -> u32 {
+        self.unit_limit
+            .unwrap_or_else(|| {
+                let instructions = u32::try_from(self.other_instructions).unwrap_or(u32::MAX);
+                DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT.saturating_mul(instructions)
+            })
+            .min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    /// Priority fee in lamports: unit price × effective limit, rounded up and
+    /// saturating at `u64::MAX`.
+    pub fn priority_fee_lamports(&self) -> u64 {
+        let price = self.unit_price_micro_lamports.unwrap_or(0) as u128;
+        let micro_lamports = price * self.effective_unit_limit() as u128;
+        u64::try_from(micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT)).unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(unit_limit: Option<u32>, price: Option<u64>, other_instructions: usize) -> ComputeBudgetSettings {
+        ComputeBudgetSettings {
+            unit_limit,
+            unit_price_micro_lamports: price,
+            other_instructions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn effective_unit_limit_defaults_per_instruction_and_caps() {
+        assert_eq!(settings(None, None, 0).effective_unit_limit(), 0);
+        assert_eq!(settings(None, None, 3).effective_unit_limit(), 600_000);
+        assert_eq!(settings(None, None, 10).effective_unit_limit(), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(settings(Some(50_000), None, 10).effective_unit_limit(), 50_000);
+        assert_eq!(settings(Some(u32::MAX), None, 1).effective_unit_limit(), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn priority_fee_rounds_up_and_saturates() {
+        assert_eq!(settings(Some(200_000), None, 1).priority_fee_lamports(), 0);
+        assert_eq!(settings(Some(200_000), Some(5), 1).priority_fee_lamports(), 1);
+        assert_eq!(settings(Some(200_000), Some(5_000), 1).priority_fee_lamports(), 1_000);
+        assert_eq!(settings(Some(200_001), Some(5_000), 1).priority_fee_lamports(), 1_001);
+        assert_eq!(settings(Some(1), Some(u64::MAX), 1).priority_fee_lamports(), u64::MAX / 1_000_000 + 1);
+        assert_eq!(settings(None, Some(u64::MAX), 7).priority_fee_lamports(), u64::MAX);
+    }
+
+    #[test]
+    fn from_instructions_counts_and_flags_duplicates() {
+        let other = Pubkey::new_unique();
+        let keys = [compute_budget::id(), other];
+        let budget = |ix: solana_sdk::instruction::Instruction| CompiledInstruction::new_from_raw_parts(0, ix.data, vec![]);
+        let instructions = [
+            budget(ComputeBudgetInstruction::set_compute_unit_limit(10)),
+            budget(ComputeBudgetInstruction::set_compute_unit_limit(20)),
+            budget(ComputeBudgetInstruction::set_compute_unit_price(7)),
+            CompiledInstruction::new_from_raw_parts(1, vec![], vec![]),
+        ];
+        let settings = ComputeBudgetSettings::from_instructions(&keys, &instructions);
+        assert_eq!(settings.unit_limit, Some(20));
+        assert_eq!(settings.unit_price_micro_lamports, Some(7));
+        assert_eq!(settings.budget_instructions, 3);
+        assert_eq!(settings.other_instructions, 1);
+        assert_eq!(settings.duplicate_instructions, vec!["SetComputeUnitLimit".to_string()]);
+    }
+}
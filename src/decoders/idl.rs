@@ -0,0 +1,520 @@
+use super::{DecodedInstruction, InstructionDecoder};
+use crate::errors::ServiceError;
+
+use heck::ToSnakeCase;
+use serde_json::{json, Map, Value};
+use solana_sdk::hash::hashv;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Nested user types are resolved recursively; this bounds malicious or cyclic IDLs.
+const MAX_TYPE_DEPTH: usize = 32;
+
+/// Borsh-encoded argument types understood by the IDL decoder.
+#[derive(Debug, Clone)]
+enum IdlType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    String,
+    Bytes,
+    Pubkey,
+    Vec(Box<IdlType>),
+    Option(Box<IdlType>),
+    COption(Box<IdlType>),
+    Array(Box<IdlType>, usize),
+    Defined(String),
+}
+
+#[derive(Debug, Clone)]
+struct IdlField {
+    name: String,
+    ty: IdlType,
+}
+
+#[derive(Debug, Clone)]
+enum IdlTypeDef {
+    /// Named fields, or positional fields named `0`, `1`, ...
+    Struct(Vec<IdlField>),
+    Enum(Vec<(String, Vec<IdlField>)>),
+}
+
+struct IdlInstruction {
+    name: String,
+    discriminator: [u8; 8],
+    /// Account names flattened in instruction order; nested groups are prefixed `group.`.
+    accounts: Vec<String>,
+    args: Vec<IdlField>,
+}
+
+/// Decodes instructions of an Anchor program from its IDL by matching the
+/// 8-byte instruction discriminator.
+pub struct IdlDecoder {
+    program_name: String,
+    instructions: Vec<IdlInstruction>,
+    types: HashMap<String, IdlTypeDef>,
+}
+
+impl IdlDecoder {
+    /// Parses an Anchor IDL (legacy or 0.30+ format) and returns the program id it describes.
+    pub fn from_json(json: &str) -> Result<(Pubkey, Self), ServiceError> {
+        let idl: Value = serde_json::from_str(json)
+            .map_err(|e| ServiceError::InvalidIdl(format!("Invalid JSON: {}", e)))?;
+        let address = idl
+            .get("address")
+            .or_else(|| idl.pointer("/metadata/address"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| ServiceError::InvalidIdl("IDL has no program address".to_string()))?;
+        let program_id = Pubkey::from_str(address)
+            .map_err(|e| ServiceError::InvalidIdl(format!("Invalid program address {}: {}", address, e)))?;
+        let program_name = idl
+            .get("name")
+            .or_else(|| idl.pointer("/metadata/name"))
+            .and_then(Value::as_str)
+            .unwrap_or(address)
+            .to_string();
+        let instructions = idl
+            .get("instructions")
+            .and_then(Value::as_array)
+            .ok_or_else(|| ServiceError::InvalidIdl("IDL has no instructions".to_string()))?
+            .iter()
+            .map(parse_instruction)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut types = HashMap::new();
+        for definition in idl.get("types").and_then(Value::as_array).into_iter().flatten() {
+            let (name, type_def) = parse_type_def(definition)?;
+            types.insert(name, type_def);
+        }
+        Ok((program_id, Self { program_name, instructions, types }))
+    }
+
+    fn decode_args(&self, args: &[IdlField], data: &[u8]) -> Result<Value, String> {
+        let mut reader = Reader { data, offset: 0 };
+        let value = self.read_fields(args, &mut reader, 0)?;
+        if reader.offset != data.len() {
+            return Err(format!("{} trailing bytes after arguments", data.len() - reader.offset));
+        }
+        Ok(value)
+    }
+
+    fn read_fields(&self, fields: &[IdlField], reader: &mut Reader<'_>, depth: usize) -> Result<Value, String> {
+        let mut map = Map::new();
+        for field in fields {
+            let value = self.read(&field.ty, reader, depth)?;
+            map.insert(field.name.clone(), value);
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn read(&self, ty: &IdlType, reader: &mut Reader<'_>, depth: usize) -> Result<Value, String> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err("type nesting too deep".to_string());
+        }
+        Ok(match ty {
+            IdlType::Bool => match reader.take::<1>()?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                tag => return Err(format!("invalid bool value {}", tag)),
+            },
+            IdlType::U8 => json!(reader.take::<1>()?[0]),
+            IdlType::U16 => json!(u16::from_le_bytes(reader.take()?)),
+            IdlType::U32 => json!(u32::from_le_bytes(reader.take()?)),
+            IdlType::U64 => json!(u64::from_le_bytes(reader.take()?)),
+            // 128-bit integers do not fit in JSON numbers; render them as strings.
+            IdlType::U128 => json!(u128::from_le_bytes(reader.take()?).to_string()),
+            IdlType::I8 => json!(i8::from_le_bytes(reader.take()?)),
+            IdlType::I16 => json!(i16::from_le_bytes(reader.take()?)),
+            IdlType::I32 => json!(i32::from_le_bytes(reader.take()?)),
+            IdlType::I64 => json!(i64::from_le_bytes(reader.take()?)),
+            IdlType::I128 => json!(i128::from_le_bytes(reader.take()?).to_string()),
+            IdlType::F32 => json!(f32::from_le_bytes(reader.take()?)),
+            IdlType::F64 => json!(f64::from_le_bytes(reader.take()?)),
+            IdlType::String => {
+                let len = u32::from_le_bytes(reader.take()?) as usize;
+                let bytes = reader.take_slice(len)?;
+                Value::String(String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())?)
+            }
+            IdlType::Bytes => {
+                let len = u32::from_le_bytes(reader.take()?) as usize;
+                Value::String(hex::encode(reader.take_slice(len)?))
+            }
+            IdlType::Pubkey => Value::String(Pubkey::new_from_array(reader.take()?).to_string()),
+            IdlType::Vec(inner) => {
+                let len = u32::from_le_bytes(reader.take()?) as usize;
+                // Every element consumes at least one byte, so a larger length is malformed.
+                if len > reader.remaining() {
+                    return Err(format!("vector length {} exceeds remaining data", len));
+                }
+                let items = (0..len)
+                    .map(|_| self.read(inner, reader, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Array(items)
+            }
+            // Borsh only allows 0 (None) and 1 (Some) as the tag.
+            IdlType::Option(inner) => match reader.take::<1>()?[0] {
+                0 => Value::Null,
+                1 => self.read(inner, reader, depth + 1)?,
+                tag => return Err(format!("invalid option tag {}", tag)),
+            },
+            IdlType::COption(inner) => match u32::from_le_bytes(reader.take()?) {
+                0 => Value::Null,
+                1 => self.read(inner, reader, depth + 1)?,
+                tag => return Err(format!("invalid coption tag {}", tag)),
+            },
+            IdlType::Array(inner, len) => {
+                if *len > reader.remaining() {
+                    return Err(format!("array length {} exceeds remaining data", len));
+                }
+                let items = (0..*len)
+                    .map(|_| self.read(inner, reader, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Array(items)
+            }
+            IdlType::Defined(name) => match self.types.get(name) {
+                Some(IdlTypeDef::Struct(fields)) => self.read_fields(fields, reader, depth + 1)?,
+                Some(IdlTypeDef::Enum(variants)) => {
+                    let tag = reader.take::<1>()?[0] as usize;
+                    let (variant, fields) = variants
+                        .get(tag)
+                        .ok_or_else(|| format!("invalid variant {} for {}", tag, name))?;
+                    if fields.is_empty() {
+                        Value::String(variant.clone())
+                    } else {
+                        let mut map = Map::new();
+                        map.insert(variant.clone(), self.read_fields(fields, reader, depth + 1)?);
+                        Value::Object(map)
+                    }
+                }
+                None => return Err(format!("unknown type {}", name)),
+            },
+        })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| format!("unexpected end of data at offset {}", self.offset))?;
+        let slice = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let slice = self.take_slice(N)?;
+        Ok(slice.try_into().expect("slice has length N"))
+    }
+}
+
+fn parse_instruction(instruction: &Value) -> Result<IdlInstruction, ServiceError> {
+    let name = instruction
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ServiceError::InvalidIdl("Instruction without a name".to_string()))?
+        .to_string();
+    let discriminator = match instruction.get("discriminator") {
+        Some(value) => serde_json::from_value::<[u8; 8]>(value.clone())
+            .map_err(|e| ServiceError::InvalidIdl(format!("Invalid discriminator for {}: {}", name, e)))?,
+        None => sighash(&name),
+    };
+    let mut accounts = Vec::new();
+    if let Some(list) = instruction.get("accounts").and_then(Value::as_array) {
+        flatten_accounts(list, "", &mut accounts);
+    }
+    let args = match instruction.get("args") {
+        Some(args) => parse_fields(args)?,
+        None => Vec::new(),
+    };
+    Ok(IdlInstruction { name, discriminator, accounts, args })
+}
+
+fn flatten_accounts(list: &[Value], prefix: &str, out: &mut Vec<String>) {
+    for account in list {
+        let name = account.get("name").and_then(Value::as_str).unwrap_or("unknown");
+        match account.get("accounts").and_then(Value::as_array) {
+            Some(nested) => flatten_accounts(nested, &format!("{}{}.", prefix, name), out),
+            None => out.push(format!("{}{}", prefix, name)),
+        }
+    }
+}
+
+/// Fields are either `[{ "name", "type" }]` or, for tuple structs/variants, `[type, ...]`.
+fn parse_fields(fields: &Value) -> Result<Vec<IdlField>, ServiceError> {
+    let list = fields
+        .as_array()
+        .ok_or_else(|| ServiceError::InvalidIdl(format!("Expected field list, found {}", fields)))?;
+    list.iter()
+        .enumerate()
+        .map(|(i, field)| match (field.get("name").and_then(Value::as_str), field.get("type")) {
+            (Some(name), Some(ty)) => Ok(IdlField { name: name.to_string(), ty: parse_type(ty)? }),
+            _ => Ok(IdlField { name: i.to_string(), ty: parse_type(field)? }),
+        })
+        .collect()
+}
+
+fn parse_type(ty: &Value) -> Result<IdlType, ServiceError> {
+    let unsupported = || ServiceError::InvalidIdl(format!("Unsupported IDL type: {}", ty));
+    if let Some(name) = ty.as_str() {
+        return Ok(match name {
+            "bool" => IdlType::Bool,
+            "u8" => IdlType::U8,
+            "u16" => IdlType::U16,
+            "u32" => IdlType::U32,
+            "u64" => IdlType::U64,
+            "u128" => IdlType::U128,
+            "i8" => IdlType::I8,
+            "i16" => IdlType::I16,
+            "i32" => IdlType::I32,
+            "i64" => IdlType::I64,
+            "i128" => IdlType::I128,
+            "f32" => IdlType::F32,
+            "f64" => IdlType::F64,
+            "string" => IdlType::String,
+            "bytes" => IdlType::Bytes,
+            "publicKey" | "pubkey" => IdlType::Pubkey,
+            _ => return Err(unsupported()),
+        });
+    }
+    let object = ty.as_object().ok_or_else(unsupported)?;
+    if let Some(inner) = object.get("vec") {
+        return Ok(IdlType::Vec(Box::new(parse_type(inner)?)));
+    }
+    if let Some(inner) = object.get("option") {
+        return Ok(IdlType::Option(Box::new(parse_type(inner)?)));
+    }
+    if let Some(inner) = object.get("coption") {
+        return Ok(IdlType::COption(Box::new(parse_type(inner)?)));
+    }
+    if let Some(array) = object.get("array").and_then(Value::as_array) {
+        let (Some(inner), Some(len)) = (array.first(), array.get(1).and_then(Value::as_u64)) else {
+            return Err(unsupported());
+        };
+        return Ok(IdlType::Array(Box::new(parse_type(inner)?), len as usize));
+    }
+    if let Some(defined) = object.get("defined") {
+        // Legacy IDLs use `"defined": "Name"`, 0.30+ uses `"defined": { "name": "Name" }`.
+        let name = defined
+            .as_str()
+            .or_else(|| defined.get("name").and_then(Value::as_str))
+            .ok_or_else(unsupported)?;
+        return Ok(IdlType::Defined(name.to_string()));
+    }
+    Err(unsupported())
+}
+
+fn parse_type_def(definition: &Value) -> Result<(String, IdlTypeDef), ServiceError> {
+    let name = definition
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ServiceError::InvalidIdl("Type definition without a name".to_string()))?
+        .to_string();
+    let ty = definition
+        .get("type")
+        .ok_or_else(|| ServiceError::InvalidIdl(format!("Type {} has no body", name)))?;
+    let type_def = match ty.get("kind").and_then(Value::as_str) {
+        Some("struct") => IdlTypeDef::Struct(match ty.get("fields") {
+            Some(fields) => parse_fields(fields)?,
+            None => Vec::new(),
+        }),
+        Some("enum") => {
+            let variants = ty
+                .get("variants")
+                .and_then(Value::as_array)
+                .ok_or_else(|| ServiceError::InvalidIdl(format!("Enum {} has no variants", name)))?;
+            let variants = variants
+                .iter()
+                .map(|variant| {
+                    let variant_name = variant.get("name").and_then(Value::as_str).unwrap_or("unknown").to_string();
+                    let fields = match variant.get("fields") {
+                        Some(fields) => parse_fields(fields)?,
+                        None => Vec::new(),
+                    };
+                    Ok((variant_name, fields))
+                })
+                .collect::<Result<Vec<_>, ServiceError>>()?;
+            IdlTypeDef::Enum(variants)
+        }
+        other => {
+            return Err(ServiceError::InvalidIdl(format!("Unsupported kind {:?} for type {}", other, name)));
+        }
+    };
+    Ok((name, type_def))
+}
+
+/// Legacy IDLs omit the discriminator: it is `sha256("global:<snake_case name>")[..8]`,
+/// snake-cased with heck exactly as Anchor does (`initializeNFTMint` -> `initialize_nft_mint`).
+fn sighash(name: &str) -> [u8; 8] {
+    let preimage = format!("global:{}", name.to_snake_case());
+    let hash = hashv(&[preimage.as_bytes()]);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+impl InstructionDecoder for IdlDecoder {
+    fn program_name(&self) -> &str {
+        &self.program_name
+    }
+
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<DecodedInstruction> {
+        let discriminator = data.get(..8)?;
+        let instruction = self.instructions.iter().find(|ix| ix.discriminator == discriminator)?;
+        let mut named_accounts: Map<String, Value> = instruction
+            .accounts
+            .iter()
+            .zip(accounts)
+            .map(|(name, key)| (name.clone(), Value::from(key.to_string())))
+            .collect();
+        // Accounts past the IDL's list are typically remaining_accounts.
+        let remaining: Vec<Value> = accounts
+            .iter()
+            .skip(instruction.accounts.len())
+            .map(|key| Value::from(key.to_string()))
+            .collect();
+        if !remaining.is_empty() {
+            named_accounts.insert("remaining_accounts".to_string(), Value::Array(remaining));
+        }
+        let data = match self.decode_args(&instruction.args, &data[8..]) {
+            Ok(args) => json!({ "accounts": named_accounts, "args": args }),
+            Err(e) => json!({
+                "accounts": named_accounts,
+                "args_error": e,
+                "args_hex": hex::encode(&data[8..]),
+            }),
+        };
+        Some(DecodedInstruction {
+            name: instruction.name.clone(),
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+    fn decoder(instructions: Value, types: Value) -> IdlDecoder {
+        let idl = json!({
+            "name": "test_program",
+            "metadata": { "address": PROGRAM },
+            "instructions": instructions,
+            "types": types,
+        });
+        IdlDecoder::from_json(&idl.to_string()).unwrap().1
+    }
+
+    /// Decodes `args` for a single instruction named `run` taking `arg_types`.
+    fn decode(arg_types: Value, types: Value, args: &[u8]) -> Value {
+        let decoder = decoder(json!([{ "name": "run", "accounts": [], "args": arg_types }]), types);
+        let mut data = sighash("run").to_vec();
+        data.extend_from_slice(args);
+        decoder.decode(&data, &[]).expect("discriminator matches").data
+    }
+
+    #[test]
+    fn legacy_discriminators_match_anchor() {
+        let known: [(&str, [u8; 8]); 5] = [
+            ("initialize", [175, 175, 109, 31, 13, 152, 155, 237]),
+            ("route", [229, 23, 203, 151, 122, 227, 173, 42]),
+            ("sharedAccountsRoute", [193, 32, 155, 51, 65, 214, 156, 129]),
+            ("mintV1", [145, 98, 192, 118, 184, 147, 118, 104]),
+            ("initializeNFTMint", [128, 48, 170, 43, 45, 187, 140, 4]),
+        ];
+        for (name, discriminator) in known {
+            assert_eq!(sighash(name), discriminator, "{}", name);
+        }
+    }
+
+    #[test]
+    fn decodes_nested_structs_options_and_enums() {
+        let types = json!([
+            { "name": "Leg", "type": { "kind": "struct", "fields": [
+                { "name": "amount", "type": "u64" },
+                { "name": "memo", "type": { "option": "string" } },
+            ]}},
+            { "name": "Side", "type": { "kind": "enum", "variants": [
+                { "name": "Buy" },
+                { "name": "Sell", "fields": [{ "name": "limit", "type": "u32" }] },
+            ]}},
+        ]);
+        let args = json!([
+            { "name": "legs", "type": { "vec": { "defined": "Leg" } } },
+            { "name": "side", "type": { "defined": { "name": "Side" } } },
+            { "name": "other", "type": { "defined": "Side" } },
+        ]);
+        let mut data = vec![2, 0, 0, 0];
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&9u64.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 0, 0, 0, b'h', b'i']);
+        data.push(1);
+        data.extend_from_slice(&500u32.to_le_bytes());
+        data.push(0);
+        assert_eq!(
+            decode(args, types, &data)["args"],
+            json!({
+                "legs": [{ "amount": 7, "memo": null }, { "amount": 9, "memo": "hi" }],
+                "side": { "Sell": { "limit": 500 } },
+                "other": "Buy",
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_non_borsh_option_and_bool_tags() {
+        let option = decode(json!([{ "name": "x", "type": { "option": "u8" } }]), json!([]), &[2, 5]);
+        assert_eq!(option["args_error"], "invalid option tag 2");
+        let flag = decode(json!([{ "name": "x", "type": "bool" }]), json!([]), &[7]);
+        assert_eq!(flag["args_error"], "invalid bool value 7");
+        let unknown_variant = decode(
+            json!([{ "name": "x", "type": { "defined": "E" } }]),
+            json!([{ "name": "E", "type": { "kind": "enum", "variants": [{ "name": "A" }] } }]),
+            &[1],
+        );
+        assert_eq!(unknown_variant["args_error"], "invalid variant 1 for E");
+    }
+
+    #[test]
+    fn recursive_types_stop_at_the_depth_limit() {
+        let types = json!([{ "name": "Node", "type": { "kind": "struct", "fields": [
+            { "name": "next", "type": { "option": { "defined": "Node" } } },
+        ]}}]);
+        let args = json!([{ "name": "head", "type": { "defined": "Node" } }]);
+        let shallow = decode(args.clone(), types.clone(), &[1, 1, 0]);
+        assert_eq!(shallow["args"], json!({ "head": { "next": { "next": { "next": null } } } }));
+        let deep = decode(args, types, &[1; 64]);
+        assert_eq!(deep["args_error"], "type nesting too deep");
+    }
+
+    #[test]
+    fn trailing_bytes_are_an_error() {
+        let decoded = decode(json!([{ "name": "x", "type": "u8" }]), json!([]), &[1, 2]);
+        assert_eq!(decoded["args_error"], "1 trailing bytes after arguments");
+        assert_eq!(decoded["args_hex"], "0102");
+    }
+}
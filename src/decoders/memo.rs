@@ -0,0 +1,32 @@
+use super::{DecodedInstruction, InstructionDecoder};
+
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+pub const MEMO_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("MemoSq4gqABAXKb96qnH8TusnWdzYUp5GF4VEZGKGHu");
+pub const MEMO_V1_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("Memo1UhkJRfHyvLMcVj6sCHQh1z6Wa4Wcj1Aadfp7fv");
+
+pub fn is_memo_program(program_id: &Pubkey) -> bool {
+    *program_id == MEMO_PROGRAM_ID || *program_id == MEMO_V1_PROGRAM_ID
+}
+
+pub fn decode_memo(data: &[u8]) -> Option<String> {
+    std::str::from_utf8(data).ok().map(str::to_string)
+}
+
+pub struct MemoDecoder;
+
+impl InstructionDecoder for MemoDecoder {
+    fn program_name(&self) -> &str {
+        "spl-memo"
+    }
+
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<DecodedInstruction> {
+        let memo = decode_memo(data)?;
+        let signers: Vec<String> = accounts.iter().map(|k| k.to_string()).collect();
+        Some(DecodedInstruction {
+            name: "Memo".to_string(),
+            data: json!({ "memo": memo, "signers": signers }),
+        })
+    }
+}
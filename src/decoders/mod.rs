@@ -0,0 +1,182 @@
+use crate::errors::ServiceError;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+pub mod associated_token;
+pub mod compute_budget;
+pub mod idl;
+pub mod memo;
+pub mod system;
+pub mod token;
+
+pub use idl::IdlDecoder;
+
+/// Program-specific interpretation of an instruction's data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// Instruction data shown when no decoder recognises it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawInstructionData {
+    pub hex: String,
+    pub base58: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionView {
+    pub index: usize,
+    pub program_id: String,
+    pub program: Option<String>,
+    pub accounts: Vec<String>,
+    pub decoded: Option<DecodedInstruction>,
+    pub raw: Option<RawInstructionData>,
+}
+
+impl InstructionView {
+    /// One-line description used in logs.
+    pub fn summary(&self) -> String {
+        let program = self.program.as_deref().unwrap_or(&self.program_id);
+        match (&self.decoded, &self.raw) {
+            (Some(decoded), _) if decoded.data.is_null() => format!("{}::{}", program, decoded.name),
+            (Some(decoded), _) => format!("{}::{} {}", program, decoded.name, decoded.data),
+            (None, Some(raw)) => format!("{} raw data hex={} base58={}", program, raw.hex, raw.base58),
+            (None, None) => format!("{} (no data)", program),
+        }
+    }
+}
+
+pub trait InstructionDecoder: Send + Sync {
+    /// Human readable program name, e.g. `system`.
+    fn program_name(&self) -> &str;
+
+    /// Returns `None` when `data` is not an instruction this decoder recognises.
+    /// `accounts` are the instruction's accounts in order.
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<DecodedInstruction>;
+}
+
+/// Instruction decoders keyed by program id.
+#[derive(Default)]
+pub struct DecoderRegistry {
+    decoders: HashMap<Pubkey, Arc<dyn InstructionDecoder>>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with decoders for System, Compute Budget, SPL Token, Token-2022,
+    /// Associated Token Account and Memo programs.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(system_program::id(), Arc::new(system::SystemDecoder));
+        registry.register(solana_sdk::compute_budget::id(), Arc::new(compute_budget::ComputeBudgetDecoder));
+        for program_id in [token::TOKEN_PROGRAM_ID, token::TOKEN_2022_PROGRAM_ID] {
+            registry.register(program_id, Arc::new(token::TokenDecoder::new(program_id)));
+        }
+        registry.register(
+            associated_token::ASSOCIATED_TOKEN_PROGRAM_ID,
+            Arc::new(associated_token::AssociatedTokenDecoder),
+        );
+        for program_id in [memo::MEMO_PROGRAM_ID, memo::MEMO_V1_PROGRAM_ID] {
+            registry.register(program_id, Arc::new(memo::MemoDecoder));
+        }
+        registry
+    }
+
+    /// Registers `decoder` for `program_id`, replacing any existing decoder.
+    pub fn register(&mut self, program_id: Pubkey, decoder: Arc<dyn InstructionDecoder>) {
+        self.decoders.insert(program_id, decoder);
+    }
+
+    /// Loads an Anchor IDL JSON file and registers a decoder for the program it describes.
+    pub fn register_idl_file(&mut self, path: &str) -> Result<Pubkey, ServiceError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| ServiceError::InvalidIdl(format!("Failed to read {}: {}", path, e)))?;
+        let (program_id, decoder) = IdlDecoder::from_json(&json)?;
+        info!("Registered IDL decoder for {} ({}) from {}", decoder.program_name(), program_id, path);
+        self.register(program_id, Arc::new(decoder));
+        Ok(program_id)
+    }
+
+    /// Registers every `*.json` IDL in `dir`. Returns the number of programs loaded.
+    pub fn register_idl_dir(&mut self, dir: &str) -> Result<usize, ServiceError> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| ServiceError::InvalidIdl(format!("Failed to read IDL directory {}: {}", dir, e)))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in &paths {
+            self.register_idl_file(&path.to_string_lossy())?;
+        }
+        Ok(paths.len())
+    }
+
+    pub fn decode(&self, index: usize, program_id: &Pubkey, data: &[u8], accounts: &[Pubkey]) -> InstructionView {
+        let decoder = self.decoders.get(program_id);
+        let decoded = decoder.and_then(|d| d.decode(data, accounts));
+        let raw = (decoded.is_none() && !data.is_empty()).then(|| RawInstructionData {
+            hex: hex::encode(data),
+            base58: solana_sdk::bs58::encode(data).into_string(),
+        });
+        InstructionView {
+            index,
+            program_id: program_id.to_string(),
+            program: decoder.map(|d| d.program_name().to_string()),
+            accounts: accounts.iter().map(|k| k.to_string()).collect(),
+            decoded,
+            raw,
+        }
+    }
+
+    /// Decodes every instruction in `message`.
+    pub fn decode_message(&self, message: &Message) -> Vec<InstructionView> {
+        self.decode_instructions(&message.account_keys, &message.instructions)
+    }
+
+    /// Decodes compiled instructions against `account_keys`. Accounts that are not
+    /// in `account_keys` (v0 lookup table entries) are listed as `unresolved:<index>`
+    /// and decoders only see the accounts before the first unresolved one.
+    pub fn decode_instructions(&self, account_keys: &[Pubkey], instructions: &[CompiledInstruction]) -> Vec<InstructionView> {
+        instructions
+            .iter()
+            .enumerate()
+            .map(|(index, ix)| {
+                let program_id = account_keys
+                    .get(ix.program_id_index as usize)
+                    .copied()
+                    .unwrap_or_default();
+                let resolved: Vec<Option<Pubkey>> = ix
+                    .accounts
+                    .iter()
+                    .map(|i| account_keys.get(*i as usize).copied())
+                    .collect();
+                let accounts: Vec<Pubkey> = resolved.iter().map_while(|k| *k).collect();
+                let mut view = self.decode(index, &program_id, &ix.data, &accounts);
+                view.accounts = resolved
+                    .iter()
+                    .zip(&ix.accounts)
+                    .map(|(key, i)| match key {
+                        Some(key) => key.to_string(),
+                        None => format!("unresolved:{}", i),
+                    })
+                    .collect();
+                view
+            })
+            .collect()
+    }
+}
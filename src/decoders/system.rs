@@ -0,0 +1,46 @@
+use super::{DecodedInstruction, InstructionDecoder};
+
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction::SystemInstruction;
+
+pub struct SystemDecoder;
+
+impl InstructionDecoder for SystemDecoder {
+    fn program_name(&self) -> &str {
+        "system"
+    }
+
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<DecodedInstruction> {
+        let instruction = bincode::deserialize::<SystemInstruction>(data).ok()?;
+        let (name, mut data) = split_variant(serde_json::to_value(&instruction).ok()?);
+        let endpoints = match instruction {
+            SystemInstruction::Transfer { .. } => Some((0, 1)),
+            SystemInstruction::TransferWithSeed { .. } => Some((0, 2)),
+            SystemInstruction::CreateAccount { .. } | SystemInstruction::CreateAccountWithSeed { .. } => Some((0, 1)),
+            SystemInstruction::WithdrawNonceAccount(_) => Some((0, 1)),
+            _ => None,
+        };
+        if let (Some((from, to)), Value::Object(fields)) = (endpoints, &mut data) {
+            fields.insert("source".to_string(), accounts.get(from).map(|k| k.to_string()).into());
+            fields.insert("destination".to_string(), accounts.get(to).map(|k| k.to_string()).into());
+        }
+        Some(DecodedInstruction { name, data })
+    }
+}
+
+/// Splits serde's externally tagged enum encoding into `(variant, fields)`.
+pub(super) fn split_variant(value: Value) -> (String, Value) {
+    match value {
+        Value::String(name) => (name, Value::Null),
+        Value::Object(map) if map.len() == 1 => {
+            let (name, fields) = map.into_iter().next().unwrap_or_default();
+            let fields = match fields {
+                Value::Object(_) => fields,
+                other => serde_json::json!({ "value": other }),
+            };
+            (name, fields)
+        }
+        other => ("Unknown".to_string(), other),
+    }
+}
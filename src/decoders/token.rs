@@ -0,0 +1,306 @@
+use super::{DecodedInstruction, InstructionDecoder};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == TOKEN_PROGRAM_ID || *program_id == TOKEN_2022_PROGRAM_ID
+}
+
+/// SPL Token instructions, shared by Token and Token-2022 (tags 0-24).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenInstruction {
+    InitializeMint { decimals: u8 },
+    InitializeAccount,
+    InitializeMultisig { m: u8 },
+    Transfer { amount: u64 },
+    Approve { amount: u64 },
+    Revoke,
+    SetAuthority,
+    MintTo { amount: u64 },
+    Burn { amount: u64 },
+    CloseAccount,
+    FreezeAccount,
+    ThawAccount,
+    TransferChecked { amount: u64, decimals: u8 },
+    ApproveChecked { amount: u64, decimals: u8 },
+    MintToChecked { amount: u64, decimals: u8 },
+    BurnChecked { amount: u64, decimals: u8 },
+    InitializeAccount2,
+    SyncNative,
+    InitializeAccount3,
+    InitializeMultisig2 { m: u8 },
+    InitializeMint2 { decimals: u8 },
+    GetAccountDataSize,
+    InitializeImmutableOwner,
+    AmountToUiAmount { amount: u64 },
+    UiAmountToAmount,
+    /// Token-2022 extension instructions, identified by tag only. The legacy
+    /// program has none, so its unknown tags do not unpack.
+    Extension(u8),
+}
+
+impl TokenInstruction {
+    pub fn unpack(program_id: &Pubkey, data: &[u8]) -> Option<Self> {
+        let (&tag, rest) = data.split_first()?;
+        let amount = || rest.get(..8).and_then(|b| b.try_into().ok()).map(u64::from_le_bytes);
+        let byte = |i: usize| rest.get(i).copied();
+        Some(match tag {
+            0 => Self::InitializeMint { decimals: byte(0)? },
+            1 => Self::InitializeAccount,
+            2 => Self::InitializeMultisig { m: byte(0)? },
+            3 => Self::Transfer { amount: amount()? },
+            4 => Self::Approve { amount: amount()? },
+            5 => Self::Revoke,
+            6 => Self::SetAuthority,
+            7 => Self::MintTo { amount: amount()? },
+            8 => Self::Burn { amount: amount()? },
+            9 => Self::CloseAccount,
+            10 => Self::FreezeAccount,
+            11 => Self::ThawAccount,
+            12 => Self::TransferChecked { amount: amount()?, decimals: byte(8)? },
+            13 => Self::ApproveChecked { amount: amount()?, decimals: byte(8)? },
+            14 => Self::MintToChecked { amount: amount()?, decimals: byte(8)? },
+            15 => Self::BurnChecked { amount: amount()?, decimals: byte(8)? },
+            16 => Self::InitializeAccount2,
+            17 => Self::SyncNative,
+            18 => Self::InitializeAccount3,
+            19 => Self::InitializeMultisig2 { m: byte(0)? },
+            20 => Self::InitializeMint2 { decimals: byte(0)? },
+            21 => Self::GetAccountDataSize,
+            22 => Self::InitializeImmutableOwner,
+            23 => Self::AmountToUiAmount { amount: amount()? },
+            24 => Self::UiAmountToAmount,
+            other if *program_id == TOKEN_2022_PROGRAM_ID => Self::Extension(other),
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::InitializeMint { .. } => "InitializeMint",
+            Self::InitializeAccount => "InitializeAccount",
+            Self::InitializeMultisig { .. } => "InitializeMultisig",
+            Self::Transfer { .. } => "Transfer",
+            Self::Approve { .. } => "Approve",
+            Self::Revoke => "Revoke",
+            Self::SetAuthority => "SetAuthority",
+            Self::MintTo { .. } => "MintTo",
+            Self::Burn { .. } => "Burn",
+            Self::CloseAccount => "CloseAccount",
+            Self::FreezeAccount => "FreezeAccount",
+            Self::ThawAccount => "ThawAccount",
+            Self::TransferChecked { .. } => "TransferChecked",
+            Self::ApproveChecked { .. } => "ApproveChecked",
+            Self::MintToChecked { .. } => "MintToChecked",
+            Self::BurnChecked { .. } => "BurnChecked",
+            Self::InitializeAccount2 => "InitializeAccount2",
+            Self::SyncNative => "SyncNative",
+            Self::InitializeAccount3 => "InitializeAccount3",
+            Self::InitializeMultisig2 { .. } => "InitializeMultisig2",
+            Self::InitializeMint2 { .. } => "InitializeMint2",
+            Self::GetAccountDataSize => "GetAccountDataSize",
+            Self::InitializeImmutableOwner => "InitializeImmutableOwner",
+            Self::AmountToUiAmount { .. } => "AmountToUiAmount",
+            Self::UiAmountToAmount => "UiAmountToAmount",
+            Self::Extension(_) => "Extension",
+        }
+    }
+}
+
+/// A token amount moved, minted or burned by a Token or Token-2022 instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenTransfer {
+    pub program: String,
+    pub kind: String,
+    pub source: Option<String>,
+    pub destination: Option<String>,
+    pub authority: Option<String>,
+    /// Only known for checked instructions and mint/burn, which reference the mint account.
+    pub mint: Option<String>,
+    pub amount: u64,
+    pub decimals: Option<u8>,
+}
+
+/// Builds a `TokenTransfer` for amount-bearing instructions. `account` resolves
+/// an instruction account position to its pubkey.
+pub fn token_transfer(
+    program_id: &Pubkey,
+    instruction: &TokenInstruction,
+    account: impl Fn(usize) -> Option<Pubkey>,
+) -> Option<TokenTransfer> {
+    let key = |i: usize| account(i).map(|k| k.to_string());
+    // (kind, source, destination, authority, mint, amount, decimals)
+    let (kind, source, destination, authority, mint, amount, decimals) = match *instruction {
+        TokenInstruction::Transfer { amount } => ("transfer", key(0), key(1), key(2), None, amount, None),
+        TokenInstruction::TransferChecked { amount, decimals } => {
+            ("transfer_checked", key(0), key(2), key(3), key(1), amount, Some(decimals))
+        }
+        TokenInstruction::MintTo { amount } => ("mint_to", None, key(1), key(2), key(0), amount, None),
+        TokenInstruction::MintToChecked { amount, decimals } => {
+            ("mint_to_checked", None, key(1), key(2), key(0), amount, Some(decimals))
+        }
+        TokenInstruction::Burn { amount } => ("burn", key(0), None, key(2), key(1), amount, None),
+        TokenInstruction::BurnChecked { amount, decimals } => {
+            ("burn_checked", key(0), None, key(2), key(1), amount, Some(decimals))
+        }
+        _ => return None,
+    };
+    let program = if *program_id == TOKEN_2022_PROGRAM_ID { "spl-token-2022" } else { "spl-token" };
+    Some(TokenTransfer {
+        program: program.to_string(),
+        kind: kind.to_string(),
+        source,
+        destination,
+        authority,
+        mint,
+        amount,
+        decimals,
+    })
+}
+
+pub struct TokenDecoder {
+    program_id: Pubkey,
+}
+
+impl TokenDecoder {
+    pub fn new(program_id: Pubkey) -> Self {
+        Self { program_id }
+    }
+}
+
+impl InstructionDecoder for TokenDecoder {
+    fn program_name(&self) -> &str {
+        if self.program_id == TOKEN_2022_PROGRAM_ID { "spl-token-2022" } else { "spl-token" }
+    }
+
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<DecodedInstruction> {
+        let instruction = TokenInstruction::unpack(&self.program_id, data)?;
+        let data = match token_transfer(&self.program_id, &instruction, |i| accounts.get(i).copied()) {
+            Some(transfer) => serde_json::to_value(transfer).unwrap_or_default(),
+            None => match instruction {
+                TokenInstruction::CloseAccount => json!({
+                    "account": accounts.first().map(|k| k.to_string()),
+                    "destination": accounts.get(1).map(|k| k.to_string()),
+                    "owner": accounts.get(2).map(|k| k.to_string()),
+                }),
+                TokenInstruction::Approve { amount } => json!({ "amount": amount }),
+                TokenInstruction::ApproveChecked { amount, decimals } => json!({ "amount": amount, "decimals": decimals }),
+                TokenInstruction::InitializeMint { decimals } | TokenInstruction::InitializeMint2 { decimals } => {
+                    json!({ "decimals": decimals })
+                }
+                TokenInstruction::Extension(tag) => json!({ "tag": tag }),
+                _ => serde_json::Value::Null,
+            },
+        };
+        Some(DecodedInstruction {
+            name: instruction.name().to_string(),
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Instruction data as the SPL Token program lays it out: tag, then the
+    /// little-endian amount, then decimals for the checked variants.
+    fn data(tag: u8, amount: u64, decimals: Option<u8>) -> Vec<u8> {
+        let mut data = vec![tag];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend(decimals);
+        data
+    }
+
+    fn accounts(count: usize) -> Vec<Pubkey> {
+        (0..count).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    fn transfer(program_id: &Pubkey, data: &[u8], accounts: &[Pubkey]) -> Option<TokenTransfer> {
+        let instruction = TokenInstruction::unpack(program_id, data)?;
+        token_transfer(program_id, &instruction, |i| accounts.get(i).copied())
+    }
+
+    #[test]
+    fn transfer_reads_amount_and_account_positions() {
+        let amount = 0x0102_0304_0506_0708;
+        assert_eq!(TokenInstruction::unpack(&TOKEN_PROGRAM_ID, &data(3, amount, None)), Some(TokenInstruction::Transfer { amount }));
+        // source, destination, authority
+        let keys = accounts(3);
+        let transfer = transfer(&TOKEN_PROGRAM_ID, &data(3, amount, None), &keys).unwrap();
+        assert_eq!(transfer.program, "spl-token");
+        assert_eq!(transfer.kind, "transfer");
+        assert_eq!(transfer.amount, amount);
+        assert_eq!(transfer.source, Some(keys[0].to_string()));
+        assert_eq!(transfer.destination, Some(keys[1].to_string()));
+        assert_eq!(transfer.authority, Some(keys[2].to_string()));
+        assert_eq!((transfer.mint, transfer.decimals), (None, None));
+    }
+
+    #[test]
+    fn transfer_checked_reads_decimals_and_mint() {
+        assert_eq!(
+            TokenInstruction::unpack(&TOKEN_PROGRAM_ID, &data(12, 1_500_000, Some(6))),
+            Some(TokenInstruction::TransferChecked { amount: 1_500_000, decimals: 6 })
+        );
+        // source, mint, destination, authority
+        let keys = accounts(4);
+        let transfer = transfer(&TOKEN_2022_PROGRAM_ID, &data(12, 1_500_000, Some(6)), &keys).unwrap();
+        assert_eq!(transfer.program, "spl-token-2022");
+        assert_eq!(transfer.kind, "transfer_checked");
+        assert_eq!((transfer.amount, transfer.decimals), (1_500_000, Some(6)));
+        assert_eq!(transfer.source, Some(keys[0].to_string()));
+        assert_eq!(transfer.mint, Some(keys[1].to_string()));
+        assert_eq!(transfer.destination, Some(keys[2].to_string()));
+        assert_eq!(transfer.authority, Some(keys[3].to_string()));
+    }
+
+    #[test]
+    fn mint_and_burn_account_positions() {
+        // mint, destination, authority
+        let keys = accounts(3);
+        let mint_to = transfer(&TOKEN_PROGRAM_ID, &data(7, 5, None), &keys).unwrap();
+        assert_eq!(mint_to.mint, Some(keys[0].to_string()));
+        assert_eq!(mint_to.destination, Some(keys[1].to_string()));
+        assert_eq!(mint_to.source, None);
+        // account, mint, authority
+        let burn = transfer(&TOKEN_PROGRAM_ID, &data(15, 5, Some(2)), &keys).unwrap();
+        assert_eq!(burn.source, Some(keys[0].to_string()));
+        assert_eq!(burn.mint, Some(keys[1].to_string()));
+        assert_eq!(burn.authority, Some(keys[2].to_string()));
+        assert_eq!(burn.decimals, Some(2));
+    }
+
+    #[test]
+    fn truncated_payloads_are_rejected() {
+        let full = data(12, 42, Some(9));
+        // Missing the decimals byte, then part of the amount, then everything.
+        for len in [full.len() - 1, 5, 1, 0] {
+            assert_eq!(TokenInstruction::unpack(&TOKEN_PROGRAM_ID, &full[..len]), None, "{} bytes", len);
+        }
+        assert_eq!(TokenInstruction::unpack(&TOKEN_PROGRAM_ID, &data(3, 42, None)[..8]), None);
+        assert_eq!(TokenInstruction::unpack(&TOKEN_PROGRAM_ID, &[0]), None, "InitializeMint without decimals");
+    }
+
+    #[test]
+    fn unknown_tags_are_extensions_without_transfers() {
+        let instruction = TokenInstruction::unpack(&TOKEN_2022_PROGRAM_ID, &[200, 1, 2, 3]).unwrap();
+        assert_eq!(instruction, TokenInstruction::Extension(200));
+        assert_eq!(token_transfer(&TOKEN_2022_PROGRAM_ID, &instruction, |_| None), None);
+        let decoded = TokenDecoder::new(TOKEN_2022_PROGRAM_ID).decode(&[200], &[]).unwrap();
+        assert_eq!(decoded.data, json!({ "tag": 200 }));
+    }
+    #[test]
+    fn unknown_tags_on_the_legacy_program_do_not_decode() {
+        assert_eq!(TokenInstruction::unpack(&TOKEN_PROGRAM_ID, &[25]), None);
+        assert_eq!(TokenInstruction::unpack(&TOKEN_2022_PROGRAM_ID, &[25]), Some(TokenInstruction::Extension(25)));
+        let view = crate::decoders::DecoderRegistry::with_builtin().decode(0, &TOKEN_PROGRAM_ID, &[25], &[]);
+        assert!(view.decoded.is_none());
+        assert_eq!(view.raw.unwrap().hex, "19");
+    }
+}
@@ -11,6 +11,27 @@ pub enum ServiceError {
     #[error("RPC error: {0}")]
     RpcError(String),
     
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Invalid IDL: {0}")]
+    InvalidIdl(String),
+
+    #[error("Invalid access policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("TLS error: {0}")]
+    Tls(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -21,6 +42,12 @@ impl From<solana_client::client_error::ClientError> for ServiceError {
     }
 }
 
+impl From<rusqlite::Error> for ServiceError {
+    fn from(err: rusqlite::Error) -> Self {
+        ServiceError::Storage(err.to_string())
+    }
+}
+
 impl From<solana_sdk::transaction::TransactionError> for ServiceError {
     fn from(err: solana_sdk::transaction::TransactionError) -> Self {
         ServiceError::SimulationFailed(err.to_string())
@@ -0,0 +1,359 @@
+use crate::errors::ServiceError;
+use crate::rpc_pool::RpcPool;
+use crate::transaction_store::StoreStats;
+
+use serde::Serialize;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// How long a fetched blockhash is reused before the readiness probe refreshes it.
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Endpoints further than this many slots behind the highest one are unhealthy.
+    /// Lag needs another endpoint to compare against, so it is not checked
+    /// while fewer than two endpoints report a slot.
+    pub max_slot_lag: u64,
+    /// Per-endpoint probe timeout.
+    pub rpc_timeout: Duration,
+    /// Not ready once the cached blockhash is older than this.
+    pub max_blockhash_age: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_slot_lag: 50,
+            rpc_timeout: Duration::from_secs(2),
+            max_blockhash_age: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    pub reachable: bool,
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    /// Absent when no other endpoint reported a slot to compare against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_lag: Option<u64>,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlockhashHealth {
+    pub fresh: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blockhash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageHealth {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueHealth {
+    pub enabled: bool,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+}
+
+/// Body of `/health/ready`.
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    /// Why the service is not ready; empty when it is.
+    pub reasons: Vec<String>,
+    pub rpc: Vec<EndpointHealth>,
+    pub blockhash: BlockhashHealth,
+    pub storage: StorageHealth,
+    pub admission_queue: QueueHealth,
+}
+
+/// A blocking RPC call that at most one caller runs at a time. The timeout
+/// around a blocking call cannot cancel it, so later callers join the call
+/// still in flight instead of starting another thread against a hung upstream.
+struct SingleFlight<T> {
+    in_flight: Mutex<Option<watch::Receiver<Option<T>>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> SingleFlight<T> {
+    fn new() -> Self {
+        Self { in_flight: Mutex::new(None) }
+    }
+
+    /// Waits up to `timeout` for the running call, starting `call` on the
+    /// blocking pool when none is running.
+    async fn run(&self, timeout: Duration, call: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+        let mut receiver = {
+            let mut in_flight = self.in_flight.lock().expect("health probe mutex poisoned");
+            match in_flight.as_ref() {
+                Some(running) if running.borrow().is_none() && running.has_changed().is_ok() => running.clone(),
+                _ => {
+                    let (sender, receiver) = watch::channel(None);
+                    tokio::task::spawn_blocking(move || {
+                        let _ = sender.send(Some(call()));
+                    });
+                    *in_flight = Some(receiver.clone());
+                    receiver
+                }
+            }
+        };
+        let outcome = tokio::time::timeout(timeout, receiver.wait_for(Option::is_some))
+            .await
+            .map(|result| result.map(|value| value.clone()));
+        match outcome {
+            Ok(Ok(value)) => Ok(value.expect("wait_for returned an empty value")),
+            Ok(Err(_)) => Err("probe task failed".to_string()),
+            Err(_) => Err(format!("timed out after {}ms", timeout.as_millis())),
+        }
+    }
+}
+
+/// The slot lag is measured against: the highest reported slot, once at least
+/// two endpoints answered. A lone endpoint is its own highest slot, so it
+/// would always show a lag of zero.
+fn reference_slot(slots: impl Iterator<Item = u64>) -> Option<u64> {
+    let slots: Vec<u64> = slots.collect();
+    (slots.len() >= 2).then(|| slots.into_iter().max()).flatten()
+}
+
+/// Probes upstream RPC and keeps the latest blockhash to judge readiness.
+pub struct HealthChecker {
+    rpc_pool: Arc<RpcPool>,
+    config: HealthConfig,
+    blockhash: Mutex<Option<(Hash, Instant)>>,
+    /// One slot probe per endpoint, indexed like the pool.
+    slot_probes: Vec<Arc<SingleFlight<Result<u64, String>>>>,
+    blockhash_refresh: SingleFlight<(String, Result<Hash, String>)>,
+}
+
+impl HealthChecker {
+    pub fn new(rpc_pool: Arc<RpcPool>, config: HealthConfig) -> Self {
+        Self {
+            slot_probes: (0..rpc_pool.len()).map(|_| Arc::new(SingleFlight::new())).collect(),
+            blockhash_refresh: SingleFlight::new(),
+            rpc_pool,
+            config,
+            blockhash: Mutex::new(None),
+        }
+    }
+
+    /// Asks every endpoint for its slot concurrently and compares it to the highest.
+    async fn probe_endpoints(&self) -> Vec<EndpointHealth> {
+        let probes: Vec<_> = self
+            .slot_probes
+            .iter()
+            .enumerate()
+            .map(|(index, probe)| {
+                let pool = self.rpc_pool.clone();
+                let probe = probe.clone();
+                let timeout = self.config.rpc_timeout;
+                tokio::spawn(async move {
+                    let started = Instant::now();
+                    let outcome = probe
+                        .run(timeout, move || {
+                            pool.call_at(index, "getSlot", |client| {
+                                client.get_slot_with_commitment(CommitmentConfig::processed()).map_err(|e| e.to_string())
+                            })
+                            .1
+                        })
+                        .await
+                        .and_then(|result| result);
+                    (index, started.elapsed(), outcome)
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(probes.len());
+        for (index, probe) in probes.into_iter().enumerate() {
+            results.push(
+                probe
+                    .await
+                    .unwrap_or_else(|e| (index, Duration::ZERO, Err(format!("probe task failed: {}", e)))),
+            );
+        }
+        let endpoints = self.rpc_pool.endpoints();
+        let reference = reference_slot(results.iter().filter_map(|(_, _, r)| r.as_ref().ok()).copied());
+        results
+            .into_iter()
+            .map(|(index, elapsed, outcome)| {
+                let endpoint = endpoints[index].to_string();
+                let latency_ms = elapsed.as_millis() as u64;
+                match outcome {
+                    Ok(slot) => {
+                        let slot_lag = reference.map(|highest| highest.saturating_sub(slot));
+                        EndpointHealth {
+                            endpoint,
+                            reachable: true,
+                            healthy: slot_lag.is_none_or(|lag| lag <= self.config.max_slot_lag),
+                            slot: Some(slot),
+                            slot_lag,
+                            latency_ms,
+                            error: None,
+                        }
+                    }
+                    Err(error) => EndpointHealth {
+                        endpoint,
+                        reachable: false,
+                        healthy: false,
+                        slot: None,
+                        slot_lag: None,
+                        latency_ms,
+                        error: Some(error),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Refreshes the cached blockhash when it is older than `BLOCKHASH_REFRESH`.
+    async fn blockhash_health(&self) -> BlockhashHealth {
+        let stale = self
+            .blockhash
+            .lock()
+            .expect("health blockhash mutex poisoned")
+            .is_none_or(|(_, fetched_at)| fetched_at.elapsed() >= BLOCKHASH_REFRESH);
+        if stale {
+            let pool = self.rpc_pool.clone();
+            let refresh = self.blockhash_refresh.run(self.config.rpc_timeout, move || {
+                let (endpoint, result) =
+                    pool.call("getLatestBlockhash", |client| client.get_latest_blockhash().map_err(|e| e.to_string()));
+                (endpoint.to_string(), result)
+            });
+            match refresh.await {
+                Ok((_, Ok(hash))) => {
+                    *self.blockhash.lock().expect("health blockhash mutex poisoned") = Some((hash, Instant::now()));
+                }
+                Ok((endpoint, Err(e))) => warn!("Readiness blockhash refresh via {} failed: {}", endpoint, e),
+                Err(e) => warn!("Readiness blockhash refresh failed: {}", e),
+            }
+        }
+        match *self.blockhash.lock().expect("health blockhash mutex poisoned") {
+            Some((hash, fetched_at)) => BlockhashHealth {
+                fresh: fetched_at.elapsed() <= self.config.max_blockhash_age,
+                blockhash: Some(hash.to_string()),
+                age_ms: Some(fetched_at.elapsed().as_millis() as u64),
+            },
+            None => BlockhashHealth {
+                fresh: false,
+                blockhash: None,
+                age_ms: None,
+            },
+        }
+    }
+
+    /// Ready when at least one endpoint is healthy, the blockhash is fresh and
+    /// storage answers; the queue is reported but never blocks readiness.
+    pub async fn readiness(
+        &self,
+        store: Result<StoreStats, ServiceError>,
+        queue: Option<(usize, usize)>,
+    ) -> ReadinessReport {
+        let (rpc, blockhash) = tokio::join!(self.probe_endpoints(), self.blockhash_health());
+        let storage = match store {
+            Ok(stats) => StorageHealth {
+                ok: true,
+                backend: Some(stats.backend),
+                transactions: Some(stats.transactions),
+                error: None,
+            },
+            Err(e) => StorageHealth {
+                ok: false,
+                backend: None,
+                transactions: None,
+                error: Some(e.to_string()),
+            },
+        };
+        let admission_queue = match queue {
+            Some((depth, max_depth)) => QueueHealth {
+                enabled: true,
+                depth,
+                max_depth: Some(max_depth),
+            },
+            None => QueueHealth {
+                enabled: false,
+                depth: 0,
+                max_depth: None,
+            },
+        };
+
+        let mut reasons = Vec::new();
+        if !rpc.iter().any(|e| e.healthy) {
+            reasons.push("no healthy RPC endpoint".to_string());
+        }
+        if !blockhash.fresh {
+            reasons.push("no fresh blockhash".to_string());
+        }
+        if !storage.ok {
+            reasons.push("storage unavailable".to_string());
+        }
+        ReadinessReport {
+            ready: reasons.is_empty(),
+            reasons,
+            rpc,
+            blockhash,
+            storage,
+            admission_queue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn slot_lag_needs_two_reporting_endpoints() {
+        assert_eq!(reference_slot(std::iter::empty()), None);
+        assert_eq!(reference_slot([100].into_iter()), None);
+        assert_eq!(reference_slot([100, 180, 120].into_iter()), Some(180));
+    }
+
+    #[tokio::test]
+    async fn single_flight_joins_the_running_call() {
+        let flight = Arc::new(SingleFlight::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hung = |calls: &Arc<AtomicUsize>| {
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(300));
+                7u64
+            }
+        };
+
+        let timeout = Duration::from_millis(20);
+        assert!(flight.run(timeout, hung(&calls)).await.unwrap_err().starts_with("timed out"));
+        assert!(flight.run(timeout, hung(&calls)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // The original call's result is delivered to a caller that waits long enough.
+        assert_eq!(flight.run(Duration::from_secs(5), hung(&calls)).await, Ok(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Once it has finished, the next caller starts a fresh call.
+        assert_eq!(flight.run(Duration::from_secs(5), hung(&calls)).await, Ok(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
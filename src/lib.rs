@@ -0,0 +1,26 @@
+pub mod access_policy;
+pub mod admission_queue;
+pub mod app;
+pub mod compute_budget_policy;
+pub mod config;
+pub mod decoders;
+pub mod errors;
+pub mod health;
+pub mod listener;
+pub mod logging;
+pub mod metrics;
+pub mod models;
+pub mod rate_limiter;
+pub mod retention;
+pub mod rpc_endpoints;
+pub mod rpc_pool;
+pub mod shutdown;
+#[cfg(test)]
+mod test_http;
+pub mod tip_accounts;
+pub mod tls;
+pub mod transaction_analyzer;
+pub mod transaction_display_service;
+pub mod transaction_inspector;
+pub mod transaction_store;
+pub mod validation;
@@ -0,0 +1,243 @@
+use crate::config::ServerConfig;
+use crate::errors::ServiceError;
+use crate::shutdown::Shutdown;
+use crate::tls::TlsReloader;
+
+use axum::{Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info, warn};
+
+/// Clients that have not finished the TLS handshake by then are dropped.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ports tried after the configured one when `strict_bind` is off.
+const FALLBACK_PORTS: std::ops::RangeInclusive<u16> = 3000..=3010;
+
+/// Binds the configured address. With `strict_bind` a busy port is an error;
+/// otherwise ports 3000-3010 and then an ephemeral port are tried, with a warning.
+pub async fn bind_tcp(server: &ServerConfig) -> Result<TcpListener, ServiceError> {
+    bind_tcp_with_fallback(server, FALLBACK_PORTS).await
+}
+
+async fn bind_tcp_with_fallback(
+    server: &ServerConfig,
+    fallback_ports: impl IntoIterator<Item = u16>,
+) -> Result<TcpListener, ServiceError> {
+    let ip = IpAddr::from_str(&server.bind)
+        .map_err(|e| ServiceError::InvalidConfig(format!("server.bind {:?}: {}", server.bind, e)))?;
+    let mut candidates = vec![server.port];
+    if !server.strict_bind {
+        candidates.extend(fallback_ports.into_iter().filter(|p| *p != server.port));
+        candidates.push(0);
+    }
+    for port in candidates {
+        let addr = SocketAddr::new(ip, port);
+        match TcpListener::bind(addr).await {
+            Ok(listener) => {
+                let bound = listener.local_addr().unwrap_or(addr);
+                if port == server.port {
+                    info!("Bound to {}", bound);
+                } else {
+                    warn!("Port {} unavailable, bound to fallback {}", server.port, bound);
+                }
+                return Ok(listener);
+            }
+            Err(e) if server.strict_bind => {
+                return Err(ServiceError::Internal(format!("Failed to bind {}: {}", addr, e)));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                info!("Port {} in use, trying next...", port);
+            }
+            Err(e) => error!("Failed to bind {}: {}", addr, e),
+        }
+    }
+    Err(ServiceError::Internal("Unable to bind to any port".to_string()))
+}
+
+/// Binds a Unix domain socket at `path`, replacing a stale socket file left by
+/// a previous run. A socket another server still accepts on, or any other
+/// existing file, is an error.
+#[cfg(unix)]
+pub fn bind_unix(path: &Path) -> Result<UnixListener, ServiceError> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(ServiceError::Internal(format!(
+                "{} exists and is not a socket",
+                path.display()
+            )));
+        }
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(ServiceError::Internal(format!(
+                "{} is in use by a running server",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)
+            .map_err(|e| ServiceError::Internal(format!("Failed to remove stale socket {}: {}", path.display(), e)))?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|e| ServiceError::Internal(format!("Failed to bind {}: {}", path.display(), e)))?;
+    info!("Listening on unix:{}", path.display());
+    Ok(listener)
+}
+
+/// Serves `app` on a Unix socket. axum's `serve` only accepts TCP listeners,
+/// so connections are driven by hyper directly. Once `shutdown` triggers it
+/// stops accepting, waits for open connections to finish and removes the socket file.
+#[cfg(unix)]
+pub async fn serve_unix(listener: UnixListener, app: Router, shutdown: Shutdown) {
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    error!("Unix socket accept failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = shutdown.triggered() => break,
+        };
+        let service = TowerToHyperService::new(app.clone());
+        let connection = graceful.watch(builder.serve_connection(TokioIo::new(stream), service).into_owned());
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!("Unix socket connection error: {}", e);
+            }
+        });
+    }
+    let path = listener.local_addr().ok().and_then(|addr| addr.as_pathname().map(Path::to_path_buf));
+    drop(listener);
+    if let Some(path) = path {
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    graceful.shutdown().await;
+}
+
+/// Serves `app` over TLS on `listener`, using the config current at each
+/// handshake so reloaded certificates apply to new connections. The client's
+/// [`ClientIdentity`](crate::tls::ClientIdentity), if any, is added to every request.
+/// Returns once `shutdown` has triggered and open connections have finished.
+pub async fn serve_tls(listener: TcpListener, tls: Arc<TlsReloader>, app: Router, shutdown: Shutdown) {
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    error!("TLS accept failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = shutdown.triggered() => break,
+        };
+        let acceptor = TlsAcceptor::from(tls.current());
+        let tls = tls.clone();
+        let app = app.clone();
+        let builder = builder.clone();
+        let watcher = graceful.watcher();
+        tokio::spawn(async move {
+            let stream = match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    warn!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+                Err(_) => {
+                    warn!("TLS handshake with {} timed out", peer);
+                    return;
+                }
+            };
+            let identity = tls.identify(stream.get_ref().1.peer_certificates());
+            let service = match identity {
+                Some(identity) => app.layer(Extension(identity)),
+                None => app,
+            };
+            let connection = builder.serve_connection(TokioIo::new(stream), TowerToHyperService::new(service));
+            if let Err(e) = watcher.watch(connection.into_owned()).await {
+                warn!("TLS connection error from {}: {}", peer, e);
+            }
+        });
+    }
+    drop(listener);
+    graceful.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(port: u16, strict_bind: bool) -> ServerConfig {
+        ServerConfig { bind: "127.0.0.1".to_string(), port, strict_bind, ..Default::default() }
+    }
+
+    /// Holds a port so binding it fails with `AddrInUse`.
+    fn occupy() -> (std::net::TcpListener, u16) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    #[tokio::test]
+    async fn strict_bind_fails_when_the_port_is_taken() {
+        let (_held, port) = occupy();
+        assert!(bind_tcp(&local(port, true)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn busy_port_falls_back_to_the_next_free_port_then_an_ephemeral_one() {
+        let (_held, port) = occupy();
+        let (_also_held, busy_fallback) = occupy();
+        let (released, free_fallback) = occupy();
+        drop(released);
+
+        let listener = bind_tcp_with_fallback(&local(port, false), [busy_fallback, free_fallback]).await.unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), free_fallback);
+        drop(listener);
+
+        let listener = bind_tcp_with_fallback(&local(port, false), [busy_fallback]).await.unwrap();
+        let bound = listener.local_addr().unwrap().port();
+        assert!(bound != 0 && bound != port && bound != busy_fallback);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn bind_unix_replaces_stale_sockets_but_not_live_ones_or_other_files() {
+        let path = std::env::temp_dir().join(format!("relay-listener-{}.sock", std::process::id()));
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists(), "a closed listener leaves its socket file behind");
+        let listener = bind_unix(&path).unwrap();
+
+        // A second instance must not take the path from a live server.
+        assert!(bind_unix(&path).is_err());
+        assert!(path.exists());
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(bind_unix(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
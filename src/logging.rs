@@ -0,0 +1,249 @@
+use crate::tls::ClientIdentity;
+
+use axum::http::Request;
+use base64::Engine;
+use solana_sdk::transaction::VersionedTransaction;
+use std::fmt;
+use tower_http::request_id::RequestId;
+use tracing::Span;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Keeps the span exporter alive; dropping it flushes pending spans.
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber. `format` is `json` or `text`; the filter
+/// comes from `RUST_LOG` and defaults to `info`. When built with the `otel`
+/// feature and `OTEL_EXPORTER_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`)
+/// is set, spans are also exported over OTLP/HTTP.
+pub fn init(format: &str) -> Result<TelemetryGuard, Box<dyn std::error::Error>> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let fmt_layer = if format.eq_ignore_ascii_case("json") {
+        tracing_subscriber::fmt::layer().json().boxed()
+    } else {
+        tracing_subscriber::fmt::layer().boxed()
+    };
+    let otlp_configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|v| !v.is_empty()));
+
+    #[cfg(feature = "otel")]
+    {
+        let (otel_layer, provider) = if otlp_configured {
+            let (layer, provider) = otel::layer()?;
+            (Some(layer), Some(provider))
+        } else {
+            (None, None)
+        };
+        tracing_subscriber::registry().with(filter).with(fmt_layer).with(otel_layer).init();
+        if provider.is_some() {
+            tracing::info!("Exporting spans over OTLP");
+        }
+        Ok(TelemetryGuard { provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry().with(filter).with(fmt_layer).init();
+        if otlp_configured {
+            tracing::warn!("OTLP endpoint configured but this build lacks the `otel` feature; spans are not exported");
+        }
+        Ok(TelemetryGuard::default())
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{runtime, Resource};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// Builds the OTLP/HTTP exporter, reading endpoint, headers and timeout from
+    /// the standard `OTEL_EXPORTER_OTLP_*` variables, and installs the W3C
+    /// trace context propagator.
+    pub fn layer<S>() -> Result<(impl Layer<S>, TracerProvider), opentelemetry::trace::TraceError>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let exporter = SpanExporter::builder().with_http().build()?;
+        Ok(layer_with(exporter))
+    }
+
+    /// Like [`layer`], exporting through `exporter`.
+    pub fn layer_with<S>(exporter: SpanExporter) -> (impl Layer<S>, TracerProvider)
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let service_name =
+            std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new("service.name", service_name)]))
+            .build();
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        (tracing_opentelemetry::layer().with_tracer(tracer), provider)
+    }
+}
+
+/// Root span for an HTTP request, tagged with the id set by `SetRequestIdLayer`
+/// and the mutual TLS customer, if any.
+/// With the `otel` feature it continues any trace from an incoming `traceparent` header.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or("-");
+    let client = request
+        .extensions()
+        .get::<ClientIdentity>()
+        .map(|identity| identity.customer.as_str())
+        .unwrap_or("-");
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        client = %client,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&opentelemetry_http::HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+    }
+    span
+}
+
+/// Log-safe view of a base64 transaction: its size and first signature
+/// instead of the full payload.
+pub struct RedactedTransaction<'a>(pub &'a str);
+
+impl fmt::Display for RedactedTransaction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let signature = base64::engine::general_purpose::STANDARD
+            .decode(self.0)
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<VersionedTransaction>(&bytes).ok())
+            .and_then(|tx| tx.signatures.first().map(|s| s.to_string()));
+        write!(
+            f,
+            "<transaction {} chars base64, signature={}>",
+            self.0.len(),
+            signature.as_deref().unwrap_or("unknown")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+
+    #[test]
+    fn redacted_transaction_shows_size_and_first_signature_only() {
+        let payer = Keypair::new();
+        let cosigner = Keypair::new();
+        let mut transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        transfer.accounts.push(solana_sdk::instruction::AccountMeta::new_readonly(cosigner.pubkey(), true));
+        let message = Message::new(&[transfer], Some(&payer.pubkey()));
+        let tx = Transaction::new(&[&payer, &cosigner], message, Hash::new_unique());
+        let bytes = bincode::serialize(&tx).unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+
+        let logged = RedactedTransaction(&encoded).to_string();
+        assert_eq!(
+            logged,
+            format!("<transaction {} chars base64, signature={}>", encoded.len(), tx.signatures[0])
+        );
+        // Neither the payload nor any raw signature bytes leak, and only the
+        // first signature is shown.
+        assert!(!logged.contains(&encoded[..16]));
+        for signature in &tx.signatures {
+            assert!(!logged.contains(&base64::engine::general_purpose::STANDARD.encode(signature.as_ref())));
+        }
+        assert!(!logged.contains(&tx.signatures[1].to_string()));
+
+        let garbage = "bm90IGEgdHJhbnNhY3Rpb24=";
+        assert_eq!(
+            RedactedTransaction(garbage).to_string(),
+            format!("<transaction {} chars base64, signature=unknown>", garbage.len())
+        );
+    }
+
+    /// Accepts OTLP/HTTP exports and hands over their protobuf bodies.
+    #[cfg(feature = "otel")]
+    fn otlp_stub() -> (String, std::sync::mpsc::Receiver<Vec<u8>>) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let url = crate::test_http::serve(move |body| {
+            let _ = sender.send(body);
+            String::new()
+        });
+        (format!("{}/v1/traces", url), receiver)
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn request_span_is_exported_under_the_incoming_trace() {
+        use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (url, exports) = otlp_stub();
+        let exporter = SpanExporter::builder().with_http().with_endpoint(url).build().unwrap();
+        let (layer, provider) = otel::layer_with(exporter);
+        {
+            let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+            let request = Request::get("/rpc")
+                .header("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+                .body(())
+                .unwrap();
+            let span = request_span(&request);
+            span.in_scope(|| tracing::info!("handled"));
+        }
+        // Shutting down flushes the batch; it blocks on the export.
+        tokio::task::spawn_blocking(move || provider.shutdown()).await.unwrap().unwrap();
+
+        let body = exports.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
+        let count = |field: u8, value: &[u8]| {
+            // Protobuf length-delimited field: tag (number << 3 | 2), length, bytes.
+            let needle = [&[field << 3 | 2, value.len() as u8][..], value].concat();
+            body.windows(needle.len()).filter(|window| *window == needle).count()
+        };
+        // Span fields: 1 trace_id, 4 parent_span_id, 5 name. One span was
+        // exported, and it continues the trace from `traceparent`.
+        let trace_id = hex::decode("4bf92f3577b34da6a3ce929d0e0e4736").unwrap();
+        let parent_span_id = hex::decode("00f067aa0ba902b7").unwrap();
+        assert_eq!(count(5, b"request"), 1);
+        assert_eq!(count(1, &trace_id), 1);
+        assert_eq!(count(4, &parent_span_id), 1);
+    }
+}
@@ -1,380 +1,221 @@
-use axum::{
-    routing::{post, get},
-    Router,
-    http::StatusCode,
-    Json,
-    extract::State,
-};
 use std::sync::Arc;
-use tower_http::cors::{CorsLayer, Any};
 use tracing::{info, error};
 
-mod transaction_display_service;
-mod models;
-mod rate_limiter;
-mod errors;
-mod tip_accounts;
-mod rpc_endpoints;
+use solana_transaction_service::{
+    access_policy, admission_queue, app, compute_budget_policy, config, decoders, health, listener, logging, metrics, rate_limiter,
+    retention, rpc_pool, shutdown, transaction_display_service, tls, transaction_store, validation,
+};
 
+use app::{drain, router, AppState};
 use transaction_display_service::TransactionDisplayService;
-use models::{TransactionRequest, TransactionResponse, ErrorResponse, DisplayedTransaction};
 use rate_limiter::RateLimiter;
-use serde_json::Value;
-use serde_json::json;
-use base64::Engine;
-use solana_sdk::{native_token::{lamports_to_sol, sol_to_lamports}, pubkey::Pubkey, system_instruction::SystemInstruction, system_program};
-use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
-use tip_accounts::{TIP_ACCOUNTS, MIN_TIP};
-use std::str::FromStr;
-
+use admission_queue::AdmissionQueue;
+use metrics::Metrics;
+use retention::RetentionPolicy;
+use rpc_pool::RpcPool;
+use compute_budget_policy::ComputeBudgetPolicy;
+use access_policy::AccessPolicy;
+use health::{HealthChecker, HealthConfig};
+use tls::TlsReloader;
+use shutdown::Shutdown;
+use config::{Cli, Config};
+use clap::Parser;
+use validation::{
+    AccessPolicyStage, BlockhashStage, ComputeBudgetStage, DecodeStage, FeePayerBalanceStage, SignatureStage,
+    SizeStage, TipStage, TransactionValidator, ValidationPipeline,
+};
+use decoders::DecoderRegistry;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
+    let config = match Config::load(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if cli.print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+    let telemetry = logging::init(&config.logging.format)?;
     info!("Starting Solana Transaction Display Service...");
-    let transaction_service = Arc::new(TransactionDisplayService::new()?);
-    let tps_limit: u32 = std::env::var("TPS_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<u32>().ok())
-        .unwrap_or(1);
+    if let Some(path) = &cli.config {
+        info!("Loaded configuration from {}", path.display());
+    }
+    let store = transaction_store::open_store(&config.storage.backend, &config.storage.path)?;
+    let tps_limit = config.limits.tps;
     let rate_limiter = Arc::new(RateLimiter::new(tps_limit));
     info!("Configured TPS limit: {}", tps_limit);
-    let tip_pubkeys: Vec<Pubkey> = TIP_ACCOUNTS
-        .iter()
-        .filter_map(|s| Pubkey::from_str(s).ok())
-        .collect();
-    let min_tip_lamports: u64 = sol_to_lamports(MIN_TIP);
+    let metrics = Arc::new(Metrics::new());
+    let admission_queue = if config.admission_queue.enabled {
+        let queue = Arc::new(AdmissionQueue::new(
+            rate_limiter.clone(),
+            metrics.clone(),
+            config.admission_queue.depth,
+            Duration::from_millis(config.admission_queue.max_wait_ms),
+        ));
+        queue.spawn_dispatcher();
+        Some(queue)
+    } else {
+        None
+    };
+    let tip_pubkeys = config.tip_pubkeys();
+    let min_tip_lamports = config.tips.min_lamports;
+    let compute_budget_policy = ComputeBudgetPolicy {
+        min_unit_price: config.compute_budget.min_unit_price,
+        max_unit_limit: config.compute_budget.max_unit_limit,
+        reject_duplicates: config.compute_budget.reject_duplicates,
+        priority_fee_counts_toward_tip: config.compute_budget.priority_fee_counts_toward_tip,
+    };
+    info!("Compute budget policy: {:?}", compute_budget_policy);
+    let access_policy = match &config.access_policy.file {
+        Some(path) => {
+            let policy = Arc::new(AccessPolicy::load(path, config.access_policy.audit_log.clone())?);
+            policy.spawn_reloader(Duration::from_secs(config.access_policy.reload_secs.max(1)));
+            policy
+        }
+        None => Arc::new(AccessPolicy::disabled()),
+    };
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals();
+    let rpc_pool = Arc::new(RpcPool::new(&config.rpc.endpoints, metrics.clone()));
+    info!("RPC endpoints: {}", config.rpc.endpoints.join(", "));
+    let health = Arc::new(HealthChecker::new(
+        rpc_pool.clone(),
+        HealthConfig {
+            max_slot_lag: config.health.max_slot_lag,
+            rpc_timeout: Duration::from_millis(config.health.rpc_timeout_ms),
+            max_blockhash_age: Duration::from_secs(config.health.max_blockhash_age_secs),
+        },
+    ));
+    let mut stages: Vec<Box<dyn TransactionValidator>> = vec![
+        Box::new(DecodeStage),
+        Box::new(SizeStage {
+            max_bytes: config.limits.max_transaction_bytes,
+        }),
+        Box::new(SignatureStage {
+            verify: config.validation.verify_signatures,
+        }),
+    ];
+    if config.validation.blockhash_check {
+        stages.push(Box::new(BlockhashStage::new(rpc_pool.clone(), Duration::from_secs(30))));
+    }
+    let tip_stage = TipStage {
+        tip_pubkeys: tip_pubkeys.clone(),
+        min_tip_lamports,
+        require_last: config.tips.require_last,
+        compute_budget_policy: compute_budget_policy.clone(),
+    };
+    stages.push(Box::new(tip_stage.clone()));
+    stages.push(Box::new(ComputeBudgetStage { policy: compute_budget_policy }));
+    stages.push(Box::new(AccessPolicyStage { policy: access_policy }));
+    if config.validation.fee_payer_balance_check {
+        stages.push(Box::new(FeePayerBalanceStage::new(
+            rpc_pool.clone(),
+            tip_pubkeys.clone(),
+            Duration::from_millis(config.validation.fee_payer_balance_cache_ms),
+        )));
+    }
+    let validation = Arc::new(ValidationPipeline::new(stages));
+    info!("Validation stages: {}", validation.stage_names().join(" -> "));
+    let retention_policy = RetentionPolicy {
+        max_age: config.retention.max_age_secs.map(Duration::from_secs),
+        max_count: config.retention.max_count,
+        strip_payload_after: config.retention.strip_payload_after_mins.map(|mins| Duration::from_secs(mins * 60)),
+        archive_path: config.retention.archive_path.clone().filter(|s| !s.is_empty()),
+        interval: Duration::from_secs(config.retention.interval_secs),
+    };
+    if retention_policy.is_enabled() {
+        retention::spawn_compaction(store.clone(), retention_policy, metrics.clone());
+    }
+    let mut decoders = DecoderRegistry::with_builtin();
+    if let Some(dir) = &config.idl.dir {
+        let loaded = decoders.register_idl_dir(dir)?;
+        info!("Loaded {} Anchor IDL(s) from {}", loaded, dir);
+    }
+    for path in &config.idl.files {
+        decoders.register_idl_file(path)?;
+    }
+    let decoders = Arc::new(decoders);
+    let transaction_service = Arc::new(TransactionDisplayService::new(store, tip_pubkeys.clone(), decoders.clone(), rpc_pool, metrics.clone())?);
     let state = Arc::new(AppState {
         transaction_service,
         rate_limiter,
+        admission_queue,
+        metrics,
         tip_pubkeys,
-        min_tip_lamports,
+        tip_stage,
+        validation,
+        decoders,
+        health,
+        shutdown: shutdown.clone(),
+        started_at: std::time::Instant::now(),
     });
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .route("/sendTransaction", post(send_transaction))
-        .route("/transactions", get(get_transactions))
-        .route("/transactions/:id", get(get_transaction_by_id))
-        .route("/rpc", post(json_rpc_handler))
-        .layer(cors)
-        .with_state(state);
-    let listener = bind_with_fallback().await?;
+    let app = router(state.clone());
+    let tls = if config.tls.is_enabled() {
+        let tls = Arc::new(TlsReloader::load(&config.tls)?);
+        tls.spawn_reloader(Duration::from_secs(config.tls.reload_secs.max(1)));
+        Some(tls)
+    } else {
+        None
+    };
+    let listener = listener::bind_tcp(&config.server).await?;
+    // Bound last, so a TLS or TCP bind error does not leave the socket file behind.
+    #[cfg(unix)]
+    let unix_server = match &config.server.unix_socket {
+        Some(path) => {
+            let unix_listener = listener::bind_unix(std::path::Path::new(path))?;
+            Some(tokio::spawn(listener::serve_unix(unix_listener, app.clone(), shutdown.clone())))
+        }
+        None => None,
+    };
+    // `Config::validate` rejects `server.unix_socket` on other platforms.
+    #[cfg(not(unix))]
+    let unix_server: Option<tokio::task::JoinHandle<()>> = None;
     let addr = listener.local_addr()?;
-    info!("Server listening on http://{}:{}", addr.ip(), addr.port());
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!("Server listening on {}://{}", scheme, addr);
     info!("Available endpoints:");
     info!("  GET  /health - Health check");
+    info!("  GET  /health/live - Liveness probe");
+    info!("  GET  /health/ready - Readiness probe (RPC, blockhash, storage, queue)");
+    info!("  GET  /stats - Service statistics (admission queue, storage)");
+    info!("  GET  /metrics - Prometheus metrics");
     info!("  POST /sendTransaction - Send and display a transaction");
     info!("  POST /rpc - JSON-RPC sendTransaction (base64)");
-    info!("  GET  /transactions - Get all displayed transactions");
-    info!("  GET  /transactions/:id - Get specific transaction by ID");
-    axum::serve(listener, app).await?;
-    Ok(())
-}
-async fn bind_with_fallback() -> Result<tokio::net::TcpListener, Box<dyn std::error::Error>> {
-    let preferred_port: u16 = std::env::var("PORT")
-        .ok()
-        .and_then(|s| s.parse::<u16>().ok())
-        .unwrap_or(3000);
-    let mut candidates: Vec<u16> = Vec::new();
-    candidates.push(preferred_port);
-    if preferred_port != 3000 { candidates.push(3000); }
-    for p in 3001..=3010 { candidates.push(p); }
-    candidates.push(0); 
-    for port in candidates {
-        let addr = format!("0.0.0.0:{}", port);
-        match tokio::net::TcpListener::bind(&addr).await {
-            Ok(listener) => {
-                if port == 0 {
-                    info!("Bound to ephemeral port");
-                } else {
-                    info!("Bound to {}", addr);
-                }
-                return Ok(listener);
-            }
-            Err(e) => {
-                if e.kind() != std::io::ErrorKind::AddrInUse {
-                    error!("Failed to bind {}: {}", addr, e);
-                } else {
-                    info!("Port {} in use, trying next...", port);
+    info!("  POST /decode - Inspect a transaction without sending it");
+    info!("  POST /idl/decode - Decode a single instruction with the registered decoders");
+    info!("  GET  /transactions - List displayed transactions (paginated, filterable)");
+    info!("  GET  /transactions/:id - Get specific transaction by ID or signature");
+    info!("  GET  /transactions/by-signature/:signature - Get transaction by signature");
+    let tcp_server = match tls {
+        Some(tls) => tokio::spawn(listener::serve_tls(listener, tls, app, shutdown.clone())),
+        None => {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                let signal = shutdown.clone();
+                let result = axum::serve(listener, app)
+                    .with_graceful_shutdown(async move { signal.triggered().await })
+                    .await;
+                if let Err(e) = result {
+                    error!("Server error: {}", e);
+                    shutdown.trigger();
                 }
-            }
-        }
-    }
-    Err("Unable to bind to any port".into())
-}
-#[derive(Clone)]
-struct AppState {
-    transaction_service: Arc<TransactionDisplayService>,
-    rate_limiter: Arc<RateLimiter>,
-    tip_pubkeys: Vec<Pubkey>,
-    min_tip_lamports: u64,
-}
-async fn health_check() -> StatusCode {
-    StatusCode::OK
-}
-async fn json_rpc_handler(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
-    if !state.rate_limiter.check_rate_limit().await {
-        let err = json!({
-            "jsonrpc": "2.0",
-            "id": body.get("id").cloned().unwrap_or_else(|| Value::from(1)),
-            "error": {"code": -32098, "message": "Rate limit exceeded"}
-        });
-        return Ok(Json(err));
-    }
-    info!("JSON-RPC request: {}", body);
-    let id = body.get("id").cloned().unwrap_or_else(|| Value::from(1));
-    let method = body.get("method").and_then(|m| m.as_str()).unwrap_or("");
-    if method != "sendTransaction" {
-        error!("Validation failed: method not found");
-        let err = json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "error": {"code": -32601, "message": "Method not found"}
-        });
-        return Ok(Json(err));
-    }
-    let encoded = body
-        .get("params")
-        .and_then(|p| p.as_array())
-        .and_then(|arr| arr.get(0))
-        .and_then(|v| v.as_str());
-    if let Some(e) = encoded {
-        let preview = if e.len() > 64 { format!("{}...", &e[..64]) } else { e.to_string() };
-        info!("Received sendTransaction base64 (preview 64): {}", preview);
-    }
-    let Some(encoded_tx) = encoded else {
-        error!("Validation failed: missing base64 transaction in params");
-        let err = json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "error": {"code": -32602, "message": "Invalid params: missing base64 transaction"}
-        });
-        return Ok(Json(err));
-    };
-    let decoded_bytes = match base64::engine::general_purpose::STANDARD.decode(encoded_tx) {
-        Ok(b) => b,
-        Err(e) => {
-            error!("Validation failed: invalid base64: {}", e);
-            let err = json!({
-                "jsonrpc": "2.0",
-                "id": id,
-                "error": {"code": -32602, "message": format!("Invalid base64: {}", e)}
-            });
-            return Ok(Json(err));
-        }
-    };
-    let tx: Result<solana_sdk::transaction::Transaction, _> = bincode::deserialize(&decoded_bytes);
-    let tx = match tx {
-        Ok(t) => t,
-        Err(e) => {
-            error!("Validation failed: invalid transaction format: {}", e);
-            let err = json!({
-                "jsonrpc": "2.0",
-                "id": id,
-                "error": {"code": -32602, "message": format!("Invalid transaction format: {}", e)}
-            });
-            return Ok(Json(err));
+            })
         }
     };
-    let mut tip_ok = false;
-    if let Some(message) = Some(&tx.message) {
-        for ix in &message.instructions {
-            let program_id = message.account_keys[ix.program_id_index as usize];
-            if program_id == system_program::id() {
-                if let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize::<SystemInstruction>(&ix.data) {
-                    let to_idx = ix.accounts.get(1).copied().unwrap_or(0) as usize;
-                    let to: Pubkey = message.account_keys[to_idx];
-                    if state.tip_pubkeys.iter().any(|a| *a == to) {
-                        if lamports >= state.min_tip_lamports {
-                            tip_ok = true;
-                            break;
-                        } else {
-                            error!(
-                                "Validation failed: tip too low. required>={} (~{} SOL), found {}",
-                                state.min_tip_lamports,
-                                lamports_to_sol(state.min_tip_lamports),
-                                lamports
-                            );
-                            let err = json!({
-                                "jsonrpc": "2.0",
-                                "id": id,
-                                "error": {
-                                    "code": -32000,
-                                    "message": format!(
-                                        "Tip too low: required >= {} lamports (~{} SOL), found {}",
-                                        state.min_tip_lamports,
-                                        lamports_to_sol(state.min_tip_lamports),
-                                        lamports
-                                    )
-                                }
-                            });
-                            return Ok(Json(err));
-                        }
-                    }
-                }
-            }
-        }
-    }
-    if !tip_ok {
-        error!("Validation failed: missing required tip transfer to configured account");
-        let err = json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "error": {"code": -32001, "message": "Missing required tip transfer to configured account"}
-        });
-        return Ok(Json(err));
-    }
-    if let Some(message) = Some(&tx.message) {
-        if let Some(payer) = message.account_keys.get(0) {
-            info!("Payer: {}", payer);
-        }
-        info!(
-            "Header: num_required_signatures={}, num_readonly_signed={}, num_readonly_unsigned={}",
-            message.header.num_required_signatures,
-            message.header.num_readonly_signed_accounts,
-            message.header.num_readonly_unsigned_accounts
-        );
-        info!("Recent blockhash: {}", message.recent_blockhash);
-        info!("Num instructions: {}", message.instructions.len());
 
-        for (idx, ix) in message.instructions.iter().enumerate() {
-            let program_id = message.account_keys[ix.program_id_index as usize];
-            let accounts: Vec<String> = ix
-                .accounts
-                .iter()
-                .map(|i| message.account_keys[*i as usize].to_string())
-                .collect();
-            info!("Instruction #{} program={} accounts={:?}", idx, program_id, accounts);
-
-            if program_id == system_program::id() {
-                match bincode::deserialize::<SystemInstruction>(&ix.data) {
-                    Ok(SystemInstruction::Transfer { lamports }) => {
-                        info!(
-                            "  System::Transfer lamports={} (~{} SOL)",
-                            lamports,
-                            lamports_to_sol(lamports as u64)
-                        );
-                    }
-                    Ok(other) => info!("  System instruction: {:?}", other),
-                    Err(_) => info!("  Unable to decode system instruction data"),
-                }
-            } else if program_id == compute_budget::id() {
-                match bincode::deserialize::<ComputeBudgetInstruction>(&ix.data) {
-                    Ok(ComputeBudgetInstruction::SetComputeUnitLimit(limit)) => info!("  ComputeBudget::SetComputeUnitLimit {}", limit),
-                    Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => info!("  ComputeBudget::SetComputeUnitPrice {} microlamports/cu", price),
-                    Ok(other) => info!("  ComputeBudget instruction: {:?}", other),
-                    Err(_) => info!("  Unable to decode compute budget instruction"),
-                }
-            }
-        }
-    }
-    let signature = tx
-        .signatures
-        .get(0)
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "".to_string());
-        info!("Validation success. Extracted signature: {}", signature);
-    
-    // Create TransactionRequest to call the transaction service
-    let transaction_request = TransactionRequest {
-        from_address: "".to_string(), // Will be extracted from transaction
-        to_address: "".to_string(),   // Will be extracted from transaction
-        amount: 0.0,                  // Will be calculated from transaction
-        memo: None,
-        transaction_data: Some(encoded_tx.to_string()),
-        signature: None,
-    };
-    
-    // Call the transaction service to send and confirm the transaction
-    match state.transaction_service.send_and_display_transaction(&transaction_request).await {
-        Ok(response) => {
-            let resp = json!({
-                "jsonrpc": "2.0",
-                "id": id,
-                "result": response.signature.unwrap_or_else(|| signature)
-            });
-            Ok(Json(resp))
-        }
-        Err(e) => {
-            error!("Transaction service error: {:?}", e);
-            let err = json!({
-                "jsonrpc": "2.0",
-                "id": id,
-                "error": {"code": -32000, "message": format!("Transaction service failed: {}", e)}
-            });
-            Ok(Json(err))
-        }
-    }
-}
-async fn send_transaction(
-    State(state): State<Arc<AppState>>,
-    Json(request): Json<TransactionRequest>,
-) -> Result<Json<TransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Check rate limit
-    if !state.rate_limiter.check_rate_limit().await {
-        return Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            Json(ErrorResponse {
-                error: "Rate limit exceeded".to_string(),
-                message: "Too many requests per second".to_string(),
-            })
-        ));
-    }
-    match state.transaction_service.send_and_display_transaction(&request).await {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => {
-            error!("Transaction send error: {:?}", e);
-            Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Transaction send failed".to_string(),
-                    message: e.to_string(),
-                })
-            ))
-        }
-    }
-}
-async fn get_transactions(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<DisplayedTransaction>>, (StatusCode, Json<ErrorResponse>)> {
-    match state.transaction_service.get_all_transactions().await {
-        Ok(transactions) => Ok(Json(transactions)),
-        Err(e) => {
-            error!("Failed to get transactions: {:?}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Failed to retrieve transactions".to_string(),
-                    message: e.to_string(),
-                })
-            ))
-        }
-    }
-}
-async fn get_transaction_by_id(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> Result<Json<DisplayedTransaction>, (StatusCode, Json<ErrorResponse>)> {
-    match state.transaction_service.get_transaction_by_id(&id).await {
-        Ok(transaction) => Ok(Json(transaction)),
-        Err(e) => {
-            error!("Failed to get transaction {}: {:?}", id, e);
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Transaction not found".to_string(),
-                    message: e.to_string(),
-                })
-            ))
-        }
+    shutdown.triggered().await;
+    let drained = drain(&state, tcp_server, unix_server, Duration::from_secs(config.server.shutdown_timeout_secs)).await;
+    drop(telemetry);
+    if !drained {
+        // Abandoned sends may still be blocked in RPC calls, which runtime
+        // shutdown would wait for.
+        std::process::exit(1);
     }
+    Ok(())
 }
-
-
@@ -0,0 +1,339 @@
+use crate::retention::CompactionReport;
+use crate::transaction_store::StoreStats;
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 12] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: Duration) {
+        let secs = value.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (count, bound) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, separator, self.count);
+        let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, self.count);
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Decrements the in-flight gauge when dropped.
+pub struct InFlightGuard<'a>(&'a Metrics);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    admission_queued: AtomicU64,
+    admission_admitted: AtomicU64,
+    admission_rejected_full: AtomicU64,
+    admission_evicted: AtomicU64,
+    admission_timed_out: AtomicU64,
+    admission_wait_micros_total: AtomicU64,
+    admission_wait_micros_max: AtomicU64,
+    admission_wait: Mutex<Histogram>,
+    compaction_runs: AtomicU64,
+    compaction_evicted: AtomicU64,
+    compaction_archived: AtomicU64,
+    compaction_payloads_stripped: AtomicU64,
+    /// Keyed by (route, method, status).
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    rejections: Mutex<BTreeMap<String, u64>>,
+    tips_lamports: AtomicU64,
+    in_flight: AtomicU64,
+    /// Keyed by (endpoint, RPC method).
+    rpc_latency: Mutex<BTreeMap<(String, String), Histogram>>,
+    confirmation_latency: Mutex<Histogram>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_queued(&self) {
+        self.admission_queued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_admitted(&self, waited: Duration) {
+        let micros = waited.as_micros() as u64;
+        self.admission_admitted.fetch_add(1, Ordering::Relaxed);
+        self.admission_wait_micros_total.fetch_add(micros, Ordering::Relaxed);
+        self.admission_wait_micros_max.fetch_max(micros, Ordering::Relaxed);
+        self.admission_wait.lock().expect("metrics mutex poisoned").observe(waited);
+    }
+
+    pub fn record_rejected_full(&self) {
+        self.admission_rejected_full.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_evicted(&self) {
+        self.admission_evicted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_timed_out(&self) {
+        self.admission_timed_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_compaction(&self, report: &CompactionReport) {
+        self.compaction_runs.fetch_add(1, Ordering::Relaxed);
+        self.compaction_evicted.fetch_add(report.evicted as u64, Ordering::Relaxed);
+        self.compaction_archived.fetch_add(report.archived as u64, Ordering::Relaxed);
+        self.compaction_payloads_stripped.fetch_add(report.payloads_stripped as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_request(&self, route: &str, method: &str, status: u16) {
+        *self
+            .requests
+            .lock()
+            .expect("metrics mutex poisoned")
+            .entry((route.to_string(), method.to_string(), status))
+            .or_default() += 1;
+    }
+
+    /// Counts a transaction refused before sending, e.g. `rate_limited` or `tip_too_low`.
+    pub fn record_rejection(&self, reason: &str) {
+        *self
+            .rejections
+            .lock()
+            .expect("metrics mutex poisoned")
+            .entry(reason.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn record_tip(&self, lamports: u64) {
+        self.tips_lamports.fetch_add(lamports, Ordering::Relaxed);
+    }
+
+    /// Marks a transaction as in flight until the guard is dropped.
+    pub fn track_in_flight(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(self)
+    }
+
+    /// Transactions currently between send and confirmation.
+    pub fn in_flight(&self) -> u64 {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    pub fn observe_rpc(&self, endpoint: &str, method: &str, elapsed: Duration) {
+        self.rpc_latency
+            .lock()
+            .expect("metrics mutex poisoned")
+            .entry((endpoint.to_string(), method.to_string()))
+            .or_default()
+            .observe(elapsed);
+    }
+
+    pub fn observe_confirmation(&self, elapsed: Duration) {
+        self.confirmation_latency
+            .lock()
+            .expect("metrics mutex poisoned")
+            .observe(elapsed);
+    }
+
+    /// Prometheus text exposition format.
+    pub fn render_prometheus(&self, queue_depth: usize, store: Option<&StoreStats>) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "relay_http_requests_total", "counter", "HTTP requests by route, method and status.");
+        for ((route, method, status), count) in self.requests.lock().expect("metrics mutex poisoned").iter() {
+            let _ = writeln!(
+                out,
+                "relay_http_requests_total{{route=\"{}\",method=\"{}\",status=\"{}\"}} {}",
+                label(route),
+                label(method),
+                status,
+                count
+            );
+        }
+
+        header(&mut out, "relay_rejections_total", "counter", "Transactions rejected before sending, by reason.");
+        for (reason, count) in self.rejections.lock().expect("metrics mutex poisoned").iter() {
+            let _ = writeln!(out, "relay_rejections_total{{reason=\"{}\"}} {}", label(reason), count);
+        }
+
+        header(&mut out, "relay_tips_lamports_total", "counter", "Tips carried by sent transactions, in lamports.");
+        let _ = writeln!(out, "relay_tips_lamports_total {}", self.tips_lamports.load(Ordering::Relaxed));
+
+        header(&mut out, "relay_transactions_in_flight", "gauge", "Transactions being sent or confirmed.");
+        let _ = writeln!(out, "relay_transactions_in_flight {}", self.in_flight.load(Ordering::Relaxed));
+
+        header(&mut out, "relay_rpc_request_duration_seconds", "histogram", "Upstream RPC latency by endpoint and method.");
+        for ((endpoint, method), histogram) in self.rpc_latency.lock().expect("metrics mutex poisoned").iter() {
+            let labels = format!("endpoint=\"{}\",method=\"{}\"", label(endpoint), label(method));
+            histogram.render(&mut out, "relay_rpc_request_duration_seconds", &labels);
+        }
+
+        header(&mut out, "relay_confirmation_duration_seconds", "histogram", "Time from send to confirmed status.");
+        self.confirmation_latency
+            .lock()
+            .expect("metrics mutex poisoned")
+            .render(&mut out, "relay_confirmation_duration_seconds", "");
+
+        header(&mut out, "relay_admission_queue_depth", "gauge", "Requests waiting in the admission queue.");
+        let _ = writeln!(out, "relay_admission_queue_depth {}", queue_depth);
+        header(&mut out, "relay_admission_total", "counter", "Admission queue outcomes.");
+        for (outcome, counter) in [
+            ("queued", &self.admission_queued),
+            ("admitted", &self.admission_admitted),
+            ("rejected_full", &self.admission_rejected_full),
+            ("evicted", &self.admission_evicted),
+            ("timed_out", &self.admission_timed_out),
+        ] {
+            let _ = writeln!(out, "relay_admission_total{{outcome=\"{}\"}} {}", outcome, counter.load(Ordering::Relaxed));
+        }
+        header(&mut out, "relay_admission_wait_seconds", "histogram", "Time admitted requests spent in the admission queue.");
+        self.admission_wait
+            .lock()
+            .expect("metrics mutex poisoned")
+            .render(&mut out, "relay_admission_wait_seconds", "");
+
+        if let Some(store) = store {
+            header(&mut out, "relay_stored_transactions", "gauge", "Transactions in the store.");
+            let _ = writeln!(out, "relay_stored_transactions{{backend=\"{}\"}} {}", label(store.backend), store.transactions);
+            header(&mut out, "relay_store_bytes", "gauge", "Approximate size of the store.");
+            let _ = writeln!(out, "relay_store_bytes{{backend=\"{}\"}} {}", label(store.backend), store.approx_bytes);
+        }
+        out
+    }
+
+    pub fn snapshot(&self, queue_depth: usize, store: Option<&StoreStats>) -> Value {
+        let admitted = self.admission_admitted.load(Ordering::Relaxed);
+        let wait_total = self.admission_wait_micros_total.load(Ordering::Relaxed);
+        let avg_wait_micros = wait_total.checked_div(admitted).unwrap_or(0);
+        json!({
+            "admission_queue": {
+                "depth": queue_depth,
+                "queued": self.admission_queued.load(Ordering::Relaxed),
+                "admitted": admitted,
+                "rejected_full": self.admission_rejected_full.load(Ordering::Relaxed),
+                "evicted": self.admission_evicted.load(Ordering::Relaxed),
+                "timed_out": self.admission_timed_out.load(Ordering::Relaxed),
+                "wait_micros_total": wait_total,
+                "wait_micros_avg": avg_wait_micros,
+                "wait_micros_max": self.admission_wait_micros_max.load(Ordering::Relaxed),
+            },
+            "store": store,
+            "compaction": {
+                "runs": self.compaction_runs.load(Ordering::Relaxed),
+                "evicted": self.compaction_evicted.load(Ordering::Relaxed),
+                "archived": self.compaction_archived.load(Ordering::Relaxed),
+                "payloads_stripped": self.compaction_payloads_stripped.load(Ordering::Relaxed),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sample lines of `name`, i.e. lines starting with it that are not comments.
+    fn samples<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+        text.lines().filter(|line| line.starts_with(name)).collect()
+    }
+
+    #[test]
+    fn prometheus_text_format() {
+        let metrics = Metrics::new();
+        metrics.record_request("/rpc", "POST", 200);
+        metrics.record_request("/rpc", "POST", 200);
+        metrics.record_request("/transactions/:id", "GET", 404);
+        metrics.record_rejection("tip_too_low");
+        metrics.record_rejection("odd \"reason\"\\\n");
+        for millis in [250, 500, 2_000] {
+            metrics.observe_rpc("http://rpc.test", "getBalance", Duration::from_millis(millis));
+        }
+        metrics.record_admitted(Duration::from_millis(500));
+        let text = metrics.render_prometheus(3, None);
+
+        // Every family is described once, before its samples.
+        for (name, kind) in [
+            ("relay_http_requests_total", "counter"),
+            ("relay_rejections_total", "counter"),
+            ("relay_rpc_request_duration_seconds", "histogram"),
+            ("relay_admission_queue_depth", "gauge"),
+            ("relay_admission_wait_seconds", "histogram"),
+        ] {
+            let type_line = format!("# TYPE {} {}", name, kind);
+            assert_eq!(text.matches(&type_line).count(), 1, "{}", name);
+            assert_eq!(text.matches(&format!("# HELP {} ", name)).count(), 1, "{}", name);
+            let first_sample = text.find(&format!("\n{}", name)).unwrap();
+            assert!(text.find(&type_line).unwrap() < first_sample, "{}", name);
+        }
+
+        assert_eq!(
+            samples(&text, "relay_http_requests_total"),
+            vec![
+                "relay_http_requests_total{route=\"/rpc\",method=\"POST\",status=\"200\"} 2",
+                "relay_http_requests_total{route=\"/transactions/:id\",method=\"GET\",status=\"404\"} 1",
+            ]
+        );
+        assert_eq!(
+            samples(&text, "relay_rejections_total"),
+            vec![
+                "relay_rejections_total{reason=\"odd \\\"reason\\\"\\\\\\n\"} 1",
+                "relay_rejections_total{reason=\"tip_too_low\"} 1",
+            ]
+        );
+        assert_eq!(samples(&text, "relay_admission_queue_depth"), vec!["relay_admission_queue_depth 3"]);
+
+        // Buckets are cumulative and the +Inf bucket equals the count.
+        let labels = "endpoint=\"http://rpc.test\",method=\"getBalance\"";
+        let rpc = samples(&text, "relay_rpc_request_duration_seconds");
+        for (le, count) in [("0.1", 0), ("0.25", 1), ("0.5", 2), ("1", 2), ("2.5", 3), ("30", 3), ("+Inf", 3)] {
+            let line = format!("relay_rpc_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, le, count);
+            assert!(rpc.contains(&line.as_str()), "missing {}", line);
+        }
+        assert!(rpc.contains(&format!("relay_rpc_request_duration_seconds_sum{{{}}} 2.75", labels).as_str()));
+        assert!(rpc.contains(&format!("relay_rpc_request_duration_seconds_count{{{}}} 3", labels).as_str()));
+        assert_eq!(rpc.len(), LATENCY_BUCKETS.len() + 3);
+
+        let wait = samples(&text, "relay_admission_wait_seconds");
+        assert!(wait.contains(&"relay_admission_wait_seconds_bucket{le=\"0.25\"} 0"));
+        assert!(wait.contains(&"relay_admission_wait_seconds_bucket{le=\"0.5\"} 1"));
+        assert!(wait.contains(&"relay_admission_wait_seconds_bucket{le=\"+Inf\"} 1"));
+        assert!(wait.contains(&"relay_admission_wait_seconds_sum 0.5"));
+        assert!(wait.contains(&"relay_admission_wait_seconds_count 1"));
+    }
+}
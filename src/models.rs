@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use crate::decoders::token::TokenTransfer;
+use crate::decoders::InstructionView;
+use crate::transaction_analyzer::SolTransfer;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionRequest {
@@ -24,6 +27,11 @@ pub struct TransactionResponse {
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
+    /// Machine readable reason, e.g. `tip_too_low`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,4 +47,77 @@ pub struct DisplayedTransaction {
     pub signature: Option<String>,
     pub block_time: Option<i64>,
     pub transaction_data: Option<String>, // Base64 encoded transaction
+    #[serde(default)]
+    pub tip_account: Option<String>,
+    #[serde(default)]
+    pub tip: Option<SolTransfer>,
+    /// Sum of every signed transfer to a tip account, in lamports.
+    #[serde(default)]
+    pub tip_lamports: u64,
+    /// All SOL movements decoded from System program instructions.
+    #[serde(default)]
+    pub transfers: Vec<SolTransfer>,
+    /// Sum of `transfers`, in lamports; `amount` is the same value in SOL.
+    #[serde(default)]
+    pub total_lamports: u64,
+    /// SPL Token / Token-2022 transfers, mints and burns.
+    #[serde(default)]
+    pub token_transfers: Vec<TokenTransfer>,
+    /// Every instruction with its program, accounts and decoded arguments.
+    #[serde(default)]
+    pub instructions: Vec<InstructionView>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Query parameters accepted by `GET /transactions`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TransactionQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    pub status: Option<String>,
+    pub from_address: Option<String>,
+    pub signature: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub tip_account: Option<String>,
+    #[serde(default)]
+    pub sort: SortOrder,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionPage {
+    pub items: Vec<DisplayedTransaction>,
+    pub next_cursor: Option<String>,
+}
+
+fn default_encoding() -> String {
+    "base64".to_string()
+}
+
+/// Body of `POST /idl/decode`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodeInstructionRequest {
+    pub program_id: String,
+    /// Instruction data in `encoding` (`base64`, `base58` or `hex`).
+    pub data: String,
+    #[serde(default = "default_encoding")]
+    pub encoding: String,
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+/// Body of `POST /decode`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodeTransactionRequest {
+    /// Wire-format transaction, legacy or v0, in `encoding` (`base64` or `base58`).
+    pub transaction: String,
+    #[serde(default = "default_encoding")]
+    pub encoding: String,
 }
@@ -1,6 +1,8 @@
 use std::collections::VecDeque;
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+// Tokio's clock so tests can pause and advance time.
+use tokio::time::Instant;
 
 pub struct RateLimiter {
     max_requests_per_second: u32,
@@ -18,10 +20,46 @@ impl RateLimiter {
     }
     
     pub async fn check_rate_limit(&self) -> bool {
+        self.try_acquire()
+    }
+
+    /// Takes a slot in the current window if one is free.
+    pub fn try_acquire(&self) -> bool {
         let now = Instant::now();
         let mut q = self.timestamps.lock().expect("rate limiter mutex poisoned");
+        self.evict_expired(&mut q, now);
+
+        if q.len() < self.max_requests_per_second as usize {
+            q.push_back(now);
+            true
+        } else {
+            false
+        }
+    }
 
-        // Evict timestamps older than window
+    /// Returns the most recently taken slot, e.g. when the request it was
+    /// meant for has gone away.
+    pub fn release(&self) {
+        self.timestamps.lock().expect("rate limiter mutex poisoned").pop_back();
+    }
+
+    /// Time until the next request would be accepted; zero if a slot is free now.
+    pub fn next_available_in(&self) -> Duration {
+        let now = Instant::now();
+        let mut q = self.timestamps.lock().expect("rate limiter mutex poisoned");
+        self.evict_expired(&mut q, now);
+
+        if q.len() < self.max_requests_per_second as usize {
+            return Duration::ZERO;
+        }
+        match q.front() {
+            Some(&front) => self.window.saturating_sub(now.duration_since(front)),
+            None => self.window,
+        }
+    }
+
+    // Evict timestamps older than window
+    fn evict_expired(&self, q: &mut VecDeque<Instant>, now: Instant) {
         while let Some(&front) = q.front() {
             if now.duration_since(front) >= self.window {
                 q.pop_front();
@@ -29,12 +67,5 @@ impl RateLimiter {
                 break;
             }
         }
-
-        if q.len() < self.max_requests_per_second as usize {
-            q.push_back(now);
-            true
-        } else {
-            false
-        }
     }
 }
@@ -0,0 +1,227 @@
+use crate::{
+    errors::ServiceError,
+    metrics::Metrics,
+    models::DisplayedTransaction,
+    transaction_store::TransactionStore,
+};
+
+use chrono::Utc;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Limits applied to stored transactions by the background compaction task.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_count: Option<usize>,
+    pub strip_payload_after: Option<Duration>,
+    /// Evicted transactions are appended here as JSON lines instead of being discarded.
+    pub archive_path: Option<String>,
+    pub interval: Duration,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_count.is_some() || self.strip_payload_after.is_some()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CompactionReport {
+    pub evicted: usize,
+    pub archived: usize,
+    pub payloads_stripped: usize,
+}
+
+/// Applies `policy` to `store`. If a later eviction step or the archive write
+/// fails, the transactions already evicted are put back and the error is
+/// returned, so nothing is lost.
+pub fn compact(store: &dyn TransactionStore, policy: &RetentionPolicy) -> Result<CompactionReport, ServiceError> {
+    let now = Utc::now();
+    let mut evicted: Vec<DisplayedTransaction> = Vec::new();
+    if let Some(max_age) = policy.max_age {
+        let cutoff = now - chrono::Duration::from_std(max_age)
+            .map_err(|e| ServiceError::Internal(format!("Invalid retention max age: {}", e)))?;
+        evicted.extend(store.evict_older_than(cutoff)?);
+    }
+    if let Some(max_count) = policy.max_count {
+        match store.evict_over_count(max_count) {
+            Ok(over_count) => evicted.extend(over_count),
+            Err(e) => {
+                restore(store, evicted, "Count eviction");
+                return Err(e);
+            }
+        }
+    }
+    let archived = match &policy.archive_path {
+        Some(path) if !evicted.is_empty() => match archive(path, &evicted) {
+            Ok(archived) => archived,
+            Err(e) => {
+                restore(store, evicted, "Archive write");
+                return Err(e);
+            }
+        },
+        _ => 0,
+    };
+    let payloads_stripped = match policy.strip_payload_after {
+        Some(after) => {
+            let cutoff = now - chrono::Duration::from_std(after)
+                .map_err(|e| ServiceError::Internal(format!("Invalid payload retention: {}", e)))?;
+            store.strip_payloads_older_than(cutoff)?
+        }
+        None => 0,
+    };
+    Ok(CompactionReport {
+        evicted: evicted.len(),
+        archived,
+        payloads_stripped,
+    })
+}
+
+/// Re-inserts evicted transactions after `step` failed.
+fn restore(store: &dyn TransactionStore, transactions: Vec<DisplayedTransaction>, step: &str) {
+    if transactions.is_empty() {
+        return;
+    }
+    let count = transactions.len();
+    let mut failed = 0;
+    for transaction in transactions {
+        let id = transaction.id.clone();
+        if let Err(e) = store.insert(transaction) {
+            error!("Failed to restore transaction {} after {} failure: {}", id, step.to_lowercase(), e);
+            failed += 1;
+        }
+    }
+    error!("{} failed; restored {} of {} evicted transactions", step, count - failed, count);
+}
+
+fn archive(path: &str, transactions: &[DisplayedTransaction]) -> Result<usize, ServiceError> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| ServiceError::Storage(format!("Failed to open archive {}: {}", path, e)))?;
+    let mut lines = String::new();
+    for transaction in transactions {
+        let line = serde_json::to_string(transaction)
+            .map_err(|e| ServiceError::Internal(format!("Failed to serialize transaction: {}", e)))?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())
+        .map_err(|e| ServiceError::Storage(format!("Failed to write archive {}: {}", path, e)))?;
+    Ok(transactions.len())
+}
+
+/// Runs `compact` every `policy.interval` until the process exits.
+pub fn spawn_compaction(store: Arc<dyn TransactionStore>, policy: RetentionPolicy, metrics: Arc<Metrics>) {
+    info!("Retention enabled: {:?}", policy);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(policy.interval);
+        loop {
+            interval.tick().await;
+            let store = store.clone();
+            let policy = policy.clone();
+            match tokio::task::spawn_blocking(move || compact(store.as_ref(), &policy)).await {
+                Ok(Ok(report)) => {
+                    metrics.record_compaction(&report);
+                    if report.evicted > 0 || report.payloads_stripped > 0 {
+                        info!(
+                            "Compaction: evicted={} archived={} payloads_stripped={}",
+                            report.evicted, report.archived, report.payloads_stripped
+                        );
+                    }
+                }
+                Ok(Err(e)) => error!("Compaction failed: {}", e),
+                Err(e) => error!("Compaction task panicked: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction_store::testing::InterceptingStore;
+    use crate::transaction_store::tests::{backends, sample};
+
+    fn policy(archive_path: &str) -> RetentionPolicy {
+        RetentionPolicy {
+            max_age: None,
+            max_count: Some(1),
+            strip_payload_after: None,
+            archive_path: Some(archive_path.to_string()),
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    fn stored_ids(store: &dyn TransactionStore) -> Vec<String> {
+        let page = store
+            .query(&crate::models::TransactionQuery {
+                sort: crate::models::SortOrder::Asc,
+                ..Default::default()
+            })
+            .unwrap();
+        page.items.into_iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn failed_archive_keeps_evicted_rows() {
+        let unwritable = std::env::temp_dir().join("relay-missing-dir").join("archive.jsonl");
+        for store in backends() {
+            for (id, micros) in [("a", 1), ("b", 2), ("c", 3)] {
+                store.insert(sample(id, micros)).unwrap();
+            }
+            let result = compact(store.as_ref(), &policy(unwritable.to_str().unwrap()));
+            assert!(matches!(result, Err(ServiceError::Storage(_))), "{}", store.backend_name());
+            assert_eq!(stored_ids(store.as_ref()), ["a", "b", "c"], "{}", store.backend_name());
+        }
+    }
+
+    #[test]
+    fn failed_count_eviction_keeps_rows_evicted_by_age() {
+        for inner in backends() {
+            let store = InterceptingStore::new(inner, |method| {
+                (method == "evict_over_count").then(|| ServiceError::Storage("count eviction failed".to_string()))
+            });
+            for (id, micros) in [("a", 1), ("b", 2)] {
+                store.insert(sample(id, micros)).unwrap();
+            }
+            let policy = RetentionPolicy {
+                max_age: Some(Duration::from_secs(60)),
+                archive_path: None,
+                ..policy("")
+            };
+            let result = compact(&store, &policy);
+            assert!(matches!(result, Err(ServiceError::Storage(_))), "{}", store.backend_name());
+            assert_eq!(stored_ids(&store), ["a", "b"], "{}", store.backend_name());
+        }
+    }
+
+    #[test]
+    fn archived_rows_are_evicted() {
+        for store in backends() {
+            let name = format!("relay-archive-{}-{}.jsonl", std::process::id(), store.backend_name());
+            let path = std::env::temp_dir().join(name);
+            let _ = std::fs::remove_file(&path);
+            for (id, micros) in [("a", 1), ("b", 2), ("c", 3)] {
+                store.insert(sample(id, micros)).unwrap();
+            }
+            let report = compact(store.as_ref(), &policy(path.to_str().unwrap())).unwrap();
+            assert_eq!((report.evicted, report.archived), (2, 2));
+            assert_eq!(stored_ids(store.as_ref()), ["c"]);
+            let archived: Vec<DisplayedTransaction> = std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            let mut ids: Vec<_> = archived.into_iter().map(|t| t.id).collect();
+            ids.sort();
+            assert_eq!(ids, ["a", "b"]);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::metrics::Metrics;
+
+use rand::Rng;
+use solana_client::rpc_client::RpcClient;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Shared RPC clients, one per upstream endpoint.
+pub struct RpcPool {
+    clients: Vec<(String, RpcClient)>,
+    metrics: Arc<Metrics>,
+}
+
+impl RpcPool {
+    pub fn new(endpoints: &[String], metrics: Arc<Metrics>) -> Self {
+        assert!(!endpoints.is_empty(), "RPC pool needs at least one endpoint");
+        Self {
+            clients: endpoints
+                .iter()
+                .map(|endpoint| (endpoint.clone(), RpcClient::new(endpoint.clone())))
+                .collect(),
+            metrics,
+        }
+    }
+
+    pub fn endpoints(&self) -> Vec<&str> {
+        self.clients.iter().map(|(endpoint, _)| endpoint.as_str()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Runs `f` against a random endpoint and records its latency under `method`.
+    /// Returns the endpoint used alongside the result.
+    pub fn call<T>(&self, method: &str, f: impl FnOnce(&RpcClient) -> T) -> (&str, T) {
+        let index = rand::thread_rng().gen_range(0..self.clients.len());
+        self.call_at(index, method, f)
+    }
+
+    /// Like [`RpcPool::call`], but against the endpoint at `index`.
+    pub fn call_at<T>(&self, index: usize, method: &str, f: impl FnOnce(&RpcClient) -> T) -> (&str, T) {
+        let (endpoint, client) = &self.clients[index];
+        let _span = tracing::info_span!("rpc", endpoint = endpoint.as_str(), method).entered();
+        let started = Instant::now();
+        let result = f(client);
+        self.metrics.observe_rpc(endpoint, method, started.elapsed());
+        (endpoint, result)
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Shared shutdown flag. Listeners stop accepting once it is triggered and
+/// `/health/ready` starts failing so load balancers move traffic away.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            sender: watch::channel(false).0,
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolves once shutdown has been triggered.
+    pub async fn triggered(&self) {
+        let mut receiver = self.sender.subscribe();
+        // The sender lives in `self`, so the channel cannot close while we wait.
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Triggers shutdown on the first SIGTERM or SIGINT (Ctrl-C on platforms
+    /// without Unix signals). A second signal exits immediately without waiting
+    /// for the drain.
+    pub fn listen_for_signals(&self) {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            let mut signals = match Signals::install() {
+                Ok(signals) => signals,
+                Err(e) => {
+                    error!("Failed to install signal handlers: {}", e);
+                    return;
+                }
+            };
+            let name = signals.recv().await;
+            info!("Received {}, shutting down", name);
+            shutdown.trigger();
+            signals.recv().await;
+            warn!("Received second signal, exiting without draining");
+            std::process::exit(130);
+        });
+    }
+}
+
+#[cfg(unix)]
+struct Signals {
+    sigterm: Signal,
+    sigint: Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    fn install() -> std::io::Result<Self> {
+        Ok(Self {
+            sigterm: signal(SignalKind::terminate())?,
+            sigint: signal(SignalKind::interrupt())?,
+        })
+    }
+
+    /// Waits for the next SIGTERM or SIGINT and returns its name.
+    async fn recv(&mut self) -> &'static str {
+        tokio::select! {
+            _ = self.sigterm.recv() => "SIGTERM",
+            _ = self.sigint.recv() => "SIGINT",
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    fn install() -> std::io::Result<Self> {
+        Ok(Self)
+    }
+
+    /// Waits for the next Ctrl-C. If the handler cannot be registered this
+    /// never resolves, so the service keeps running.
+    async fn recv(&mut self) -> &'static str {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+        "Ctrl-C"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn trigger_wakes_every_subscriber() {
+        let shutdown = Shutdown::new();
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let shutdown = shutdown.clone();
+                tokio::spawn(async move { shutdown.triggered().await })
+            })
+            .collect();
+        tokio::task::yield_now().await;
+        assert!(!shutdown.is_triggered());
+        assert!(waiters.iter().all(|waiter| !waiter.is_finished()));
+
+        shutdown.trigger();
+        for waiter in waiters {
+            tokio::time::timeout(Duration::from_secs(5), waiter).await.unwrap().unwrap();
+        }
+        // Subscribing after the fact resolves immediately.
+        assert!(shutdown.is_triggered());
+        tokio::time::timeout(Duration::from_secs(5), shutdown.triggered()).await.unwrap();
+    }
+}
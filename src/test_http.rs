@@ -0,0 +1,41 @@
+//! Minimal HTTP/1.1 server for tests that stand in for upstream services.
+
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Serves every connection to a local port on a background thread, answering
+/// each request with 200 and the body `respond` returns for the request body
+/// (sent as JSON unless empty). Returns the base URL.
+pub(crate) fn serve(respond: impl Fn(Vec<u8>) -> String + Send + 'static) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let response = respond(body);
+            let content_type = if response.is_empty() { "" } else { "content-type: application/json\r\n" };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+                content_type,
+                response.len(),
+                response
+            );
+        }
+    });
+    url
+}
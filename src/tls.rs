@@ -0,0 +1,354 @@
+use crate::config::TlsConfig;
+use crate::errors::ServiceError;
+
+use rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, warn};
+
+/// Who is on the other end of a mutually authenticated connection. Added to the
+/// extensions of every request on that connection.
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+    /// Customer from `tls.clients_by_fingerprint` or `tls.clients_by_cn`, or
+    /// [`UNMAPPED_CUSTOMER`] when the certificate is in neither.
+    pub customer: String,
+    pub subject_cn: Option<String>,
+    /// Hex SHA-256 of the leaf certificate.
+    pub fingerprint: String,
+}
+
+/// Customer recorded for client certificates missing from both maps. Not the
+/// subject CN, which any certificate from the client CA may claim.
+pub const UNMAPPED_CUSTOMER: &str = "unmapped";
+
+/// Lookup key for `tls.clients_by_fingerprint`: lowercase, without the colons
+/// `openssl x509 -fingerprint` prints.
+fn fingerprint_key(value: &str) -> String {
+    value.replace(':', "").to_ascii_lowercase()
+}
+
+/// Customers by certificate fingerprint and by subject CN. The two are kept
+/// apart so a certificate whose CN spells another client's fingerprint cannot
+/// take on that client's identity.
+#[derive(Debug, Default)]
+struct ClientDirectory {
+    by_fingerprint: HashMap<String, String>,
+    by_cn: HashMap<String, String>,
+}
+
+impl ClientDirectory {
+    fn from_config(settings: &TlsConfig) -> Self {
+        Self {
+            by_fingerprint: settings
+                .clients_by_fingerprint
+                .iter()
+                .map(|(fingerprint, customer)| (fingerprint_key(fingerprint), customer.clone()))
+                .collect(),
+            by_cn: settings.clients_by_cn.clone().into_iter().collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.by_fingerprint.is_empty() && self.by_cn.is_empty()
+    }
+
+    fn customer(&self, fingerprint: &str, subject_cn: Option<&str>) -> Option<&String> {
+        self.by_fingerprint
+            .get(fingerprint)
+            .or_else(|| subject_cn.and_then(|cn| self.by_cn.get(cn)))
+    }
+}
+
+fn tls_error(message: impl Into<String>) -> ServiceError {
+    ServiceError::Tls(message.into())
+}
+
+fn open(path: &str) -> Result<BufReader<File>, ServiceError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| tls_error(format!("Failed to open {}: {}", path, e)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>, ServiceError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .map_err(|e| tls_error(format!("Failed to parse {}: {}", path, e)))?;
+    if certs.is_empty() {
+        return Err(tls_error(format!("No certificates found in {}", path)));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_key(path: &str) -> Result<PrivateKey, ServiceError> {
+    let mut reader = open(path)?;
+    loop {
+        match rustls_pemfile::read_one(&mut reader).map_err(|e| tls_error(format!("Failed to parse {}: {}", path, e)))? {
+            Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(tls_error(format!("No private key found in {}", path))),
+        }
+    }
+}
+
+/// Builds the rustls config from the files named in `settings`.
+fn build_server_config(settings: &TlsConfig) -> Result<ServerConfig, ServiceError> {
+    let (Some(cert_path), Some(key_path)) = (&settings.cert_path, &settings.key_path) else {
+        return Err(tls_error("tls.cert_path and tls.key_path are both required"));
+    };
+    let certs = load_certs(cert_path)?;
+    let key = load_key(key_path)?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &settings.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_path)? {
+                roots
+                    .add(&cert)
+                    .map_err(|e| tls_error(format!("Invalid CA certificate in {}: {}", ca_path, e)))?;
+            }
+            let verifier = if settings.require_client_cert {
+                AllowAnyAuthenticatedClient::new(roots).boxed()
+            } else {
+                AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed()
+            };
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| tls_error(format!("Invalid certificate or key: {}", e)))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Current TLS server config, rebuilt when the certificate, key or client CA
+/// file changes.
+pub struct TlsReloader {
+    settings: TlsConfig,
+    config: RwLock<Arc<ServerConfig>>,
+    modified: RwLock<Vec<Option<SystemTime>>>,
+    clients: ClientDirectory,
+}
+
+impl TlsReloader {
+    pub fn load(settings: &TlsConfig) -> Result<Self, ServiceError> {
+        let config = build_server_config(settings)?;
+        let reloader = Self {
+            settings: settings.clone(),
+            config: RwLock::new(Arc::new(config)),
+            modified: RwLock::new(Vec::new()),
+            clients: ClientDirectory::from_config(settings),
+        };
+        *reloader.modified.write().expect("tls lock poisoned") = reloader.file_times();
+        info!(
+            "TLS enabled (client certificates: {})",
+            match (&settings.client_ca_path, settings.require_client_cert) {
+                (None, _) => "off",
+                (Some(_), true) => "required",
+                (Some(_), false) => "optional",
+            }
+        );
+        Ok(reloader)
+    }
+
+    pub fn current(&self) -> Arc<ServerConfig> {
+        self.config.read().expect("tls lock poisoned").clone()
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &String> {
+        [&self.settings.cert_path, &self.settings.key_path, &self.settings.client_ca_path]
+            .into_iter()
+            .flatten()
+    }
+
+    fn file_times(&self) -> Vec<Option<SystemTime>> {
+        self.paths()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    /// Rebuilds the config from disk. On error the previous config stays in use.
+    pub fn reload(&self) -> Result<(), ServiceError> {
+        let modified = self.file_times();
+        let config = build_server_config(&self.settings)?;
+        *self.config.write().expect("tls lock poisoned") = Arc::new(config);
+        *self.modified.write().expect("tls lock poisoned") = modified;
+        info!("Reloaded TLS certificate");
+        Ok(())
+    }
+
+    /// Polls the certificate, key and CA files and reloads when any changes.
+    pub fn spawn_reloader(self: &Arc<Self>, interval: Duration) {
+        let tls = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let modified = tls.file_times();
+                if modified == *tls.modified.read().expect("tls lock poisoned") {
+                    continue;
+                }
+                if let Err(e) = tls.reload() {
+                    error!("TLS reload failed, keeping previous certificate: {}", e);
+                    *tls.modified.write().expect("tls lock poisoned") = modified;
+                }
+            }
+        });
+    }
+
+    /// Maps the peer's leaf certificate to a customer by fingerprint, then by
+    /// subject CN, falling back to [`UNMAPPED_CUSTOMER`].
+    pub fn identify(&self, peer_certs: Option<&[Certificate]>) -> Option<ClientIdentity> {
+        let leaf = peer_certs?.first()?;
+        let fingerprint = hex::encode(solana_sdk::hash::hash(&leaf.0).to_bytes());
+        let subject_cn = x509_parser::parse_x509_certificate(&leaf.0).ok().and_then(|(_, cert)| {
+            cert.subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(str::to_string)
+        });
+        let customer = match self.clients.customer(&fingerprint, subject_cn.as_deref()) {
+            Some(customer) => customer.clone(),
+            None => {
+                if !self.clients.is_empty() {
+                    warn!(
+                        "Client certificate not in tls.clients_by_fingerprint or tls.clients_by_cn (cn={}, fingerprint={})",
+                        subject_cn.as_deref().unwrap_or("-"),
+                        fingerprint
+                    );
+                }
+                UNMAPPED_CUSTOMER.to_string()
+            }
+        };
+        Some(ClientIdentity {
+            customer,
+            subject_cn,
+            fingerprint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Self-signed certificate for `cn`, as DER and PEM, with its PEM key.
+    fn certificate(cn: &str) -> (Certificate, String, String) {
+        let mut params = rcgen::CertificateParams::new(vec![cn.to_string()]);
+        params.distinguished_name.push(rcgen::DnType::CommonName, cn);
+        let cert = rcgen::Certificate::from_params(params).unwrap();
+        (
+            Certificate(cert.serialize_der().unwrap()),
+            cert.serialize_pem().unwrap(),
+            cert.serialize_private_key_pem(),
+        )
+    }
+
+    /// Writes a server certificate and key to a fresh directory and returns
+    /// settings pointing at them.
+    fn server_files(name: &str) -> TlsConfig {
+        let dir = std::env::temp_dir().join(format!("relay-tls-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (_, cert_pem, key_pem) = certificate("relay.example.com");
+        let cert_path = dir.join("server.crt");
+        let key_path = dir.join("server.key");
+        std::fs::write(&cert_path, cert_pem).unwrap();
+        std::fs::write(&key_path, key_pem).unwrap();
+        TlsConfig {
+            cert_path: Some(cert_path.to_string_lossy().into_owned()),
+            key_path: Some(key_path.to_string_lossy().into_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn remove_server_files(settings: &TlsConfig) {
+        let cert_path = std::path::Path::new(settings.cert_path.as_ref().unwrap());
+        std::fs::remove_dir_all(cert_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn cn_cannot_match_a_fingerprint_entry() {
+        let fingerprint = "ab".repeat(32);
+        let mut settings = TlsConfig::default();
+        settings.clients_by_fingerprint.insert(fingerprint.to_ascii_uppercase(), "tenant-a".to_string());
+        settings.clients_by_cn.insert("partner-b.example.com".to_string(), "tenant-b".to_string());
+        let clients = ClientDirectory::from_config(&settings);
+
+        assert_eq!(clients.customer(&fingerprint, None).map(String::as_str), Some("tenant-a"));
+        // A certificate whose CN is tenant-a's fingerprint is not tenant-a.
+        let other = "cd".repeat(32);
+        assert_eq!(clients.customer(&other, Some(&fingerprint)), None);
+        assert_eq!(
+            clients.customer(&other, Some("partner-b.example.com")).map(String::as_str),
+            Some("tenant-b")
+        );
+        // The fingerprint wins when both match.
+        assert_eq!(
+            clients.customer(&fingerprint, Some("partner-b.example.com")).map(String::as_str),
+            Some("tenant-a")
+        );
+    }
+
+    #[test]
+    fn fingerprints_accept_openssl_format() {
+        let mut settings = TlsConfig::default();
+        let openssl = (0..32).map(|_| "AB").collect::<Vec<_>>().join(":");
+        settings.clients_by_fingerprint.insert(openssl, "tenant-a".to_string());
+        let clients = ClientDirectory::from_config(&settings);
+        assert_eq!(clients.customer(&"ab".repeat(32), None).map(String::as_str), Some("tenant-a"));
+    }
+
+    #[test]
+    fn identify_maps_real_certificates_and_never_trusts_an_unmapped_cn() {
+        let (pinned, _, _) = certificate("pinned.example.com");
+        let (partner, _, _) = certificate("partner-b.example.com");
+        let (spoofed, _, _) = certificate("tenant-a");
+        let mut settings = server_files("identify");
+        let pinned_fingerprint = hex::encode(solana_sdk::hash::hash(&pinned.0).to_bytes());
+        settings.clients_by_fingerprint.insert(pinned_fingerprint.clone(), "tenant-a".to_string());
+        settings.clients_by_cn.insert("partner-b.example.com".to_string(), "tenant-b".to_string());
+        let tls = TlsReloader::load(&settings).unwrap();
+
+        let identity = tls.identify(Some(std::slice::from_ref(&pinned))).unwrap();
+        assert_eq!(identity.customer, "tenant-a");
+        assert_eq!(identity.fingerprint, pinned_fingerprint);
+        assert_eq!(identity.subject_cn.as_deref(), Some("pinned.example.com"));
+        assert_eq!(tls.identify(Some(&[partner])).unwrap().customer, "tenant-b");
+
+        // A partner-CA certificate whose CN names a customer is still unmapped.
+        let identity = tls.identify(Some(&[spoofed])).unwrap();
+        assert_eq!(identity.customer, UNMAPPED_CUSTOMER);
+        assert_eq!(identity.subject_cn.as_deref(), Some("tenant-a"));
+        assert!(tls.identify(None).is_none());
+        remove_server_files(&settings);
+    }
+
+    #[test]
+    fn failed_reload_keeps_the_previous_config() {
+        let settings = server_files("reload");
+        let tls = TlsReloader::load(&settings).unwrap();
+        let original = tls.current();
+        let cert_path = settings.cert_path.as_ref().unwrap();
+        let key_path = settings.key_path.as_ref().unwrap();
+
+        std::fs::write(cert_path, "not a certificate").unwrap();
+        assert!(tls.reload().is_err());
+        assert!(Arc::ptr_eq(&tls.current(), &original));
+
+        let (_, cert_pem, key_pem) = certificate("relay.example.com");
+        std::fs::write(cert_path, cert_pem).unwrap();
+        std::fs::write(key_path, key_pem).unwrap();
+        tls.reload().unwrap();
+        assert!(!Arc::ptr_eq(&tls.current(), &original));
+        remove_server_files(&settings);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::errors::ServiceError;
+use crate::decoders::memo;
+use crate::decoders::token::{self, TokenInstruction, TokenTransfer};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction::SystemInstruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+
+/// Lamports moved by a single System program instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolTransfer {
+    pub source: String,
+    pub destination: String,
+    pub lamports: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransactionAnalysis {
+    pub fee_payer: Pubkey,
+    /// Every SOL movement in instruction order, including tips.
+    pub transfers: Vec<SolTransfer>,
+    /// First signed transfer to a tip account.
+    pub tip: Option<SolTransfer>,
+    /// Every signed transfer to a tip account.
+    pub tips: Vec<SolTransfer>,
+    /// Sum of `tips`.
+    pub tip_lamports: u64,
+    /// Transfers to a tip account whose funding authority did not sign. These do
+    /// not count toward the tip.
+    pub unsigned_tips: Vec<SolTransfer>,
+    /// Whether the last instruction is a signed tip transfer.
+    pub tip_is_last: bool,
+    pub total_lamports: u64,
+    pub token_transfers: Vec<TokenTransfer>,
+    pub memos: Vec<String>,
+}
+
+impl TransactionAnalysis {
+    /// Destination of the first transfer that is not a tip.
+    pub fn primary_recipient(&self) -> Option<&str> {
+        self.transfers
+            .iter()
+            .find(|t| !self.tips.contains(t))
+            .or(self.tip.as_ref())
+            .map(|t| t.destination.as_str())
+    }
+}
+
+pub fn analyze(transaction: &Transaction, tip_pubkeys: &[Pubkey]) -> Result<TransactionAnalysis, ServiceError> {
+    analyze_instructions(
+        &transaction.message.account_keys,
+        transaction.message.header.num_required_signatures as usize,
+        &transaction.message.instructions,
+        tip_pubkeys,
+    )
+}
+
+/// Analyzes compiled instructions against `account_keys`, the first `num_signers`
+/// of which signed. For v0 messages pass the static keys; instructions referencing
+/// lookup table accounts are skipped.
+pub fn analyze_instructions(
+    account_keys: &[Pubkey],
+    num_signers: usize,
+    instructions: &[CompiledInstruction],
+    tip_pubkeys: &[Pubkey],
+) -> Result<TransactionAnalysis, ServiceError> {
+    let fee_payer = *account_keys
+        .first()
+        .ok_or_else(|| ServiceError::InvalidTransaction("No payer found".to_string()))?;
+
+    let mut transfers = Vec::new();
+    let mut tips = Vec::new();
+    let mut unsigned_tips = Vec::new();
+    let mut tip_is_last = false;
+    let mut token_transfers = Vec::new();
+    let mut memos = Vec::new();
+    for (index, ix) in instructions.iter().enumerate() {
+        let Some(program_id) = account_keys.get(ix.program_id_index as usize) else {
+            continue;
+        };
+        if token::is_token_program(program_id) {
+            let transfer = TokenInstruction::unpack(program_id, &ix.data).and_then(|instruction| {
+                token::token_transfer(program_id, &instruction, |i| {
+                    account_at(account_keys, &ix.accounts, i).copied()
+                })
+            });
+            token_transfers.extend(transfer);
+            continue;
+        }
+        if memo::is_memo_program(program_id) {
+            memos.extend(memo::decode_memo(&ix.data));
+            continue;
+        }
+        if *program_id != system_program::id() {
+            continue;
+        }
+        let Ok(instruction) = bincode::deserialize::<SystemInstruction>(&ix.data) else {
+            continue;
+        };
+        let Some(TransferLayout { source: source_pos, destination: destination_pos, authority, lamports }) =
+            transfer_layout(&instruction)
+        else {
+            continue;
+        };
+        let (Some(source), Some(destination)) = (
+            account_at(account_keys, &ix.accounts, source_pos),
+            account_at(account_keys, &ix.accounts, destination_pos),
+        ) else {
+            continue;
+        };
+        let transfer = SolTransfer {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            lamports,
+        };
+        let is_tip = tip_pubkeys.contains(destination)
+            && matches!(
+                instruction,
+                SystemInstruction::Transfer { .. } | SystemInstruction::TransferWithSeed { .. }
+            );
+        if is_tip {
+            let signed = ix
+                .accounts
+                .get(authority)
+                .is_some_and(|i| (*i as usize) < num_signers);
+            if signed {
+                tips.push(transfer.clone());
+                tip_is_last = index + 1 == instructions.len();
+            } else {
+                unsigned_tips.push(transfer.clone());
+            }
+        }
+        transfers.push(transfer);
+    }
+
+    let total_lamports = transfers.iter().map(|t| t.lamports).fold(0u64, u64::saturating_add);
+    let tip_lamports = tips.iter().map(|t| t.lamports).fold(0u64, u64::saturating_add);
+    Ok(TransactionAnalysis {
+        fee_payer,
+        transfers,
+        tip: tips.first().cloned(),
+        tips,
+        tip_lamports,
+        unsigned_tips,
+        tip_is_last,
+        total_lamports,
+        token_transfers,
+        memos,
+    })
+}
+
+/// Account positions for an instruction that moves lamports. `authority` is the
+/// account that must sign for the funds to move.
+struct TransferLayout {
+    source: usize,
+    destination: usize,
+    authority: usize,
+    lamports: u64,
+}
+
+fn transfer_layout(instruction: &SystemInstruction) -> Option<TransferLayout> {
+    let (source, destination, authority, lamports) = match *instruction {
+        SystemInstruction::Transfer { lamports } => (0, 1, 0, lamports),
+        // The funding account is derived from the base (account 1), which signs.
+        SystemInstruction::TransferWithSeed { lamports, .. } => (0, 2, 1, lamports),
+        SystemInstruction::CreateAccount { lamports, .. } => (0, 1, 0, lamports),
+        SystemInstruction::CreateAccountWithSeed { lamports, .. } => (0, 1, 0, lamports),
+        // The nonce authority (account 4) signs withdrawals.
+        SystemInstruction::WithdrawNonceAccount(lamports) => (0, 1, 4, lamports),
+        _ => return None,
+    };
+    Some(TransferLayout { source, destination, authority, lamports })
+}
+
+fn account_at<'a>(account_keys: &'a [Pubkey], accounts: &[u8], position: usize) -> Option<&'a Pubkey> {
+    accounts
+        .get(position)
+        .and_then(|index| account_keys.get(*index as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::system_instruction;
+
+    fn analyze_message(payer: &Pubkey, instructions: &[Instruction], tips: &[Pubkey]) -> TransactionAnalysis {
+        analyze(&Transaction::new_unsigned(Message::new(instructions, Some(payer))), tips).unwrap()
+    }
+
+    fn transfer(source: &Pubkey, destination: &Pubkey, lamports: u64) -> SolTransfer {
+        SolTransfer { source: source.to_string(), destination: destination.to_string(), lamports }
+    }
+
+    #[test]
+    fn transfer_with_seed_tip_is_authorized_by_the_base() {
+        let payer = Pubkey::new_unique();
+        let base = Pubkey::new_unique();
+        let tip = Pubkey::new_unique();
+        let from = Pubkey::create_with_seed(&base, "tips", &system_program::id()).unwrap();
+        let with_seed =
+            system_instruction::transfer_with_seed(&from, &base, "tips".to_string(), &system_program::id(), &tip, 700);
+
+        let signed = analyze_message(&payer, std::slice::from_ref(&with_seed), &[tip]);
+        assert_eq!(signed.tips, vec![transfer(&from, &tip, 700)]);
+        assert!(signed.unsigned_tips.is_empty());
+        assert!(signed.tip_is_last);
+
+        let mut unsigned_base = with_seed;
+        unsigned_base.accounts[1].is_signer = false;
+        let unsigned = analyze_message(&payer, &[unsigned_base], &[tip]);
+        assert!(unsigned.tips.is_empty());
+        assert_eq!(unsigned.unsigned_tips, vec![transfer(&from, &tip, 700)]);
+        assert_eq!(unsigned.tip_lamports, 0);
+    }
+
+    #[test]
+    fn nonce_withdrawal_is_a_transfer_but_not_a_tip() {
+        let payer = Pubkey::new_unique();
+        let nonce = Pubkey::new_unique();
+        let tip = Pubkey::new_unique();
+        let withdraw = system_instruction::withdraw_nonce_account(&nonce, &payer, &tip, 300);
+
+        let analysis = analyze_message(&payer, &[withdraw], &[tip]);
+        assert_eq!(analysis.transfers, vec![transfer(&nonce, &tip, 300)]);
+        assert!(analysis.tips.is_empty());
+        assert!(analysis.unsigned_tips.is_empty());
+        assert_eq!(analysis.total_lamports, 300);
+    }
+
+    #[test]
+    fn tips_are_summed_and_last_position_tracked() {
+        let payer = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let tips = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let first = system_instruction::transfer(&payer, &tips[0], 400);
+        let second = system_instruction::transfer(&payer, &tips[1], 600);
+        let payment = system_instruction::transfer(&payer, &other, 5);
+
+        let last = analyze_message(&payer, &[payment.clone(), first.clone(), second.clone()], &tips);
+        assert_eq!(last.tip_lamports, 1_000);
+        assert_eq!(last.tip, Some(transfer(&payer, &tips[0], 400)));
+        assert!(last.tip_is_last);
+        assert_eq!(last.primary_recipient(), Some(other.to_string().as_str()));
+
+        let not_last = analyze_message(&payer, &[first, second, payment], &tips);
+        assert_eq!(not_last.tip_lamports, 1_000);
+        assert!(!not_last.tip_is_last);
+
+        let mut unsigned = system_instruction::transfer(&other, &tips[0], 900);
+        unsigned.accounts[0].is_signer = false;
+        let trailing_unsigned =
+            analyze_message(&payer, &[system_instruction::transfer(&payer, &tips[1], 50), unsigned], &tips);
+        assert_eq!(trailing_unsigned.tip_lamports, 50);
+        assert_eq!(trailing_unsigned.unsigned_tips, vec![transfer(&other, &tips[0], 900)]);
+        assert!(!trailing_unsigned.tip_is_last);
+    }
+}
@@ -1,35 +1,46 @@
 use crate::{
-    models::{TransactionRequest, TransactionResponse, DisplayedTransaction},
+    models::{TransactionRequest, TransactionResponse, DisplayedTransaction, TransactionQuery, TransactionPage},
     errors::ServiceError,
-    rpc_endpoints,
+    metrics::Metrics,
+    rpc_pool::RpcPool,
+    decoders::DecoderRegistry,
+    transaction_analyzer,
+    transaction_store::{StoreStats, TransactionStore},
 };
 
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::sanitize::Sanitize;
 use solana_sdk::transaction::Transaction;
-use solana_client::rpc_client::RpcClient;
 use solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_sdk::commitment_config::CommitmentConfig;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
 use base64::Engine;
-use tracing::{info, error};
+use tracing::{info, error, Instrument};
 use chrono::Utc;
 use uuid::Uuid;
-use rand::Rng;
 
 pub struct TransactionDisplayService {
-    transactions: Mutex<HashMap<String, DisplayedTransaction>>,
+    store: Arc<dyn TransactionStore>,
+    tip_pubkeys: Vec<Pubkey>,
+    decoders: Arc<DecoderRegistry>,
+    rpc_pool: Arc<RpcPool>,
+    metrics: Arc<Metrics>,
 }
 
 impl TransactionDisplayService {
-    pub fn new() -> Result<Self, ServiceError> {
-        Ok(Self {
-            transactions: Mutex::new(HashMap::new()),
-        })
-    }
-    fn get_random_rpc_endpoint(&self) -> &'static str {
-        let mut rng = rand::thread_rng();
-        let index = rng.gen_range(0..rpc_endpoints::RPC_ENDPOINTS.len());
-        rpc_endpoints::RPC_ENDPOINTS[index]
+    pub fn new(
+        store: Arc<dyn TransactionStore>,
+        tip_pubkeys: Vec<Pubkey>,
+        decoders: Arc<DecoderRegistry>,
+        rpc_pool: Arc<RpcPool>,
+        metrics: Arc<Metrics>,
+    ) -> Result<Self, ServiceError> {
+        info!("Using {} transaction store", store.backend_name());
+        Ok(Self { store, tip_pubkeys, decoders, rpc_pool, metrics })
     }
     pub async fn send_and_display_transaction(
         &self,
@@ -40,38 +51,53 @@ impl TransactionDisplayService {
         let transaction_data = request.transaction_data.as_ref()
             .ok_or_else(|| ServiceError::InvalidTransaction("No transaction data provided".to_string()))?;
         let transaction = self.decode_transaction(transaction_data)?;
-        let from_address = if let Some(payer) = transaction.message.account_keys.get(0) {
-            payer.to_string()
-        } else {
-            return Err(ServiceError::InvalidTransaction("No payer found".to_string()));
-        };
-        let to_address = if let Some(recipient) = transaction.message.account_keys.get(1) {
-            recipient.to_string()
-        } else {
-            return Err(ServiceError::InvalidTransaction("No recipient found".to_string()));
-        };
-        let amount = 0.001; // 0.001 SOL
-        let signature = self.send_transaction_with_fallback(&transaction).await?;
+        let analysis = transaction_analyzer::analyze(&transaction, &self.tip_pubkeys)?;
+        info!(
+            "Analyzed transaction: payer={} transfers={} token_transfers={} total_lamports={} tip_lamports={}",
+            analysis.fee_payer,
+            analysis.transfers.len(),
+            analysis.token_transfers.len(),
+            analysis.total_lamports,
+            analysis.tip_lamports
+        );
+        let _in_flight = self.metrics.track_in_flight();
+        let sent_at = Instant::now();
+        let signature = self
+            .send_transaction_with_fallback(&transaction)
+            .instrument(tracing::info_span!("send"))
+            .await?;
         info!("Transaction sent with signature: {}", signature);
-        let transaction_status = self.confirm_transaction(&signature).await?;
+        self.metrics.record_tip(analysis.tip_lamports);
+        let transaction_status = self
+            .confirm_transaction(&signature)
+            .instrument(tracing::info_span!("confirm", signature = %signature))
+            .await?;
+        if transaction_status == "confirmed" {
+            self.metrics.observe_confirmation(sent_at.elapsed());
+        }
         let displayed_transaction = DisplayedTransaction {
             id: transaction_id.clone(),
             transaction_id: transaction_id.clone(),
-            from_address,
-            to_address,
-            amount,
-            memo: None,
+            from_address: analysis.fee_payer.to_string(),
+            to_address: analysis.primary_recipient().unwrap_or_default().to_string(),
+            amount: lamports_to_sol(analysis.total_lamports),
+            memo: request.memo.clone().or_else(|| {
+                (!analysis.memos.is_empty()).then(|| analysis.memos.join("\n"))
+            }),
             status: transaction_status,
             timestamp: Utc::now(),
             signature: Some(signature.to_string()),
             block_time: None,
             transaction_data: request.transaction_data.clone(),
+            tip_account: analysis.tip.as_ref().map(|t| t.destination.clone()),
+            tip: analysis.tip,
+            tip_lamports: analysis.tip_lamports,
+            transfers: analysis.transfers,
+            total_lamports: analysis.total_lamports,
+            token_transfers: analysis.token_transfers,
+            instructions: self.decoders.decode_message(&transaction.message),
         };
-        {
-            let mut transactions = self.transactions.lock()
-                .map_err(|e| ServiceError::Internal(format!("Failed to lock transactions: {}", e)))?;
-            transactions.insert(transaction_id.clone(), displayed_transaction);
-        }
+        self.with_store(move |store| store.insert(displayed_transaction)).await?;
         info!("Transaction stored: {}", transaction_id);
         Ok(TransactionResponse {
             transaction_id,
@@ -81,31 +107,88 @@ impl TransactionDisplayService {
             signature: Some(signature.to_string()),
         })
     }
-    pub async fn get_all_transactions(&self) -> Result<Vec<DisplayedTransaction>, ServiceError> {
-        let transactions = self.transactions.lock()
-            .map_err(|e| ServiceError::Internal(format!("Failed to lock transactions: {}", e)))?;
-        Ok(transactions.values().cloned().collect())
+    pub async fn query_transactions(&self, query: &TransactionQuery) -> Result<TransactionPage, ServiceError> {
+        let query = query.clone();
+        self.with_store(move |store| store.query(&query)).await
     }
     
+    pub async fn store_stats(&self) -> Result<StoreStats, ServiceError> {
+        self.with_store(|store| store.stats()).await
+    }
+
+    /// Runs `f` against the store on a blocking thread: the SQLite backend
+    /// blocks on its connection mutex and on disk I/O.
+    async fn with_store<T, F>(&self, f: F) -> Result<T, ServiceError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn TransactionStore) -> Result<T, ServiceError> + Send + 'static,
+    {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || f(store.as_ref()))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("Store task failed: {}", e)))?
+    }
+
+    pub fn flush_store(&self) -> Result<(), ServiceError> {
+        self.store.flush()
+    }
+
     pub async fn get_transaction_by_id(&self, id: &str) -> Result<DisplayedTransaction, ServiceError> {
-        let transactions = self.transactions.lock()
-            .map_err(|e| ServiceError::Internal(format!("Failed to lock transactions: {}", e)))?;
-        transactions.get(id)
-            .cloned()
-            .ok_or_else(|| ServiceError::InvalidTransaction(format!("Transaction not found: {}", id)))
+        let key = id.to_string();
+        let transaction = self.with_store(move |store| store.get(&key)).await?
+            .ok_or_else(|| ServiceError::NotFound(format!("transaction {}", id)))?;
+        Ok(self.refresh_status(transaction).await)
+    }
+
+    pub async fn get_transaction_by_signature(&self, signature: &str) -> Result<DisplayedTransaction, ServiceError> {
+        let key = signature.to_string();
+        let transaction = self.with_store(move |store| store.get_by_signature(&key)).await?
+            .ok_or_else(|| ServiceError::NotFound(format!("transaction with signature {}", signature)))?;
+        Ok(self.refresh_status(transaction).await)
+    }
+
+    /// Re-checks transactions still marked pending and persists any status change.
+    async fn refresh_status(&self, mut transaction: DisplayedTransaction) -> DisplayedTransaction {
+        if transaction.status != "pending" {
+            return transaction;
+        }
+        let Some(signature) = transaction.signature.as_deref().and_then(|s| Signature::from_str(s).ok()) else {
+            return transaction;
+        };
+        let confirm_span = tracing::info_span!("confirm", signature = %signature);
+        match self.confirm_transaction(&signature).instrument(confirm_span).await {
+            Ok(status) if status != transaction.status => {
+                info!("Transaction {} status changed: {} -> {}", transaction.id, transaction.status, status);
+                if status == "confirmed" {
+                    let elapsed = (Utc::now() - transaction.timestamp).to_std().unwrap_or_default();
+                    self.metrics.observe_confirmation(elapsed);
+                }
+                transaction.status = status;
+                let updated = transaction.clone();
+                if let Err(e) = self.with_store(move |store| store.insert(updated)).await {
+                    error!("Failed to persist status for {}: {}", transaction.id, e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to refresh status for {}: {}", transaction.id, e),
+        }
+        transaction
     }
     
     fn decode_transaction(&self, transaction_data: &str) -> Result<Transaction, ServiceError> {
         let transaction_bytes = base64::engine::general_purpose::STANDARD.decode(transaction_data)
             .map_err(|e| ServiceError::InvalidTransaction(format!("Base64 decode error: {}", e)))?;
         
-        bincode::deserialize::<Transaction>(&transaction_bytes)
-            .map_err(|e| ServiceError::InvalidTransaction(format!("Deserialization error: {}", e)))
+        let transaction = bincode::deserialize::<Transaction>(&transaction_bytes)
+            .map_err(|e| ServiceError::InvalidTransaction(format!("Deserialization error: {}", e)))?;
+        transaction
+            .sanitize()
+            .map_err(|e| ServiceError::InvalidTransaction(format!("Malformed message: {}", e)))?;
+        Ok(transaction)
     }
     
+    /// Sends on a blocking thread: `RpcClient` blocks through preflight and retries.
     async fn send_transaction_with_fallback(&self, transaction: &Transaction) -> Result<solana_sdk::signature::Signature, ServiceError> {
-        let endpoint = self.get_random_rpc_endpoint();
-        let client = RpcClient::new(endpoint);
         let config = RpcSendTransactionConfig {
             skip_preflight: false,
             preflight_commitment: Some(CommitmentConfig::processed().commitment),
@@ -113,8 +196,20 @@ impl TransactionDisplayService {
             max_retries: Some(3),
             min_context_slot: None,
         };
-        
-        match client.send_transaction_with_config(transaction, config) {
+        let pool = self.rpc_pool.clone();
+        let transaction = transaction.clone();
+        let span = tracing::Span::current();
+        let (endpoint, result) = tokio::task::spawn_blocking(move || {
+            span.in_scope(|| {
+                let (endpoint, result) = pool.call("sendTransaction", |client| {
+                    client.send_transaction_with_config(&transaction, config).map_err(Box::new)
+                });
+                (endpoint.to_string(), result)
+            })
+        })
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Send task failed: {}", e)))?;
+        match result {
             Ok(signature) => {
                 info!("Transaction sent successfully via {} with processed commitment", endpoint);
                 Ok(signature)
@@ -126,10 +221,23 @@ impl TransactionDisplayService {
         }
     }
     
+    /// Checks the signature status on a blocking thread, since `RpcClient` blocks
+    /// and this also runs inside GET handlers.
     async fn confirm_transaction(&self, signature: &solana_sdk::signature::Signature) -> Result<String, ServiceError> {
-        let endpoint = self.get_random_rpc_endpoint();
-        let client = RpcClient::new(endpoint);
-        match client.get_signature_status_with_commitment(signature, CommitmentConfig::processed()) {
+        let pool = self.rpc_pool.clone();
+        let signature = *signature;
+        let span = tracing::Span::current();
+        let (endpoint, result) = tokio::task::spawn_blocking(move || {
+            span.in_scope(|| {
+                let (endpoint, result) = pool.call("getSignatureStatuses", |client| {
+                    client.get_signature_status_with_commitment(&signature, CommitmentConfig::processed()).map_err(Box::new)
+                });
+                (endpoint.to_string(), result)
+            })
+        })
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Status check task failed: {}", e)))?;
+        match result {
             Ok(status) => {
                 if let Some(result) = status {
                     if result.is_ok() {
@@ -0,0 +1,218 @@
+use crate::{
+    decoders::{compute_budget::ComputeBudgetSettings, DecoderRegistry, InstructionView},
+    errors::ServiceError,
+    validation::{TipStage, ValidationError},
+};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::{MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Decodes and sanitizes a wire-format transaction (legacy or v0) from `base64` or `base58`.
+pub fn decode_versioned(encoded: &str, encoding: &str) -> Result<VersionedTransaction, ServiceError> {
+    let bytes = match encoding {
+        "base64" => base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| ServiceError::InvalidTransaction(format!("Base64 decode error: {}", e)))?,
+        "base58" => solana_sdk::bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| ServiceError::InvalidTransaction(format!("Base58 decode error: {}", e)))?,
+        other => {
+            return Err(ServiceError::InvalidTransaction(format!("Unsupported encoding: {}", other)));
+        }
+    };
+    let transaction = bincode::deserialize::<VersionedTransaction>(&bytes)
+        .map_err(|e| ServiceError::InvalidTransaction(format!("Deserialization error: {}", e)))?;
+    transaction
+        .sanitize()
+        .map_err(|e| ServiceError::InvalidTransaction(format!("Malformed message: {}", e)))?;
+    Ok(transaction)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TipStatus {
+    Ok,
+    TooLow,
+    Missing,
+    PayerNotSigner,
+    NotLast,
+    Invalid,
+}
+
+/// Whether the transaction would pass the relay's tip requirement, as
+/// evaluated by the same [`TipStage`] that `/sendTransaction` runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TipVerdict {
+    pub status: TipStatus,
+    pub required_lamports: u64,
+    /// Signed tips plus any priority fee credit; absent when no tip was found.
+    pub found_lamports: Option<u64>,
+    pub tip_account: Option<String>,
+    /// Priority fee counted toward the tip by the compute budget policy.
+    pub priority_fee_credit_lamports: u64,
+    /// The error `/sendTransaction` would return for the tip, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl TipVerdict {
+    fn evaluate(
+        tip_stage: &TipStage,
+        account_keys: &[Pubkey],
+        num_signers: usize,
+        instructions: &[CompiledInstruction],
+    ) -> Self {
+        let check = tip_stage.check(account_keys, num_signers, instructions);
+        let status = match &check.result {
+            Ok(()) => TipStatus::Ok,
+            Err(ValidationError::TipTooLow { .. }) => TipStatus::TooLow,
+            Err(ValidationError::MissingTip { .. }) => TipStatus::Missing,
+            Err(ValidationError::TipPayerNotSigner(_)) => TipStatus::PayerNotSigner,
+            Err(ValidationError::TipNotLast) => TipStatus::NotLast,
+            Err(_) => TipStatus::Invalid,
+        };
+        Self {
+            status,
+            required_lamports: tip_stage.min_tip_lamports,
+            found_lamports: (status != TipStatus::Missing).then_some(check.found_lamports),
+            tip_account: check.tip_account,
+            priority_fee_credit_lamports: check.priority_fee_credit,
+            reason: check.result.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressTableLookupView {
+    pub account_key: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// Everything the relay can tell about a transaction without sending it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionBreakdown {
+    /// `legacy` or `0`.
+    pub version: String,
+    pub header: MessageHeader,
+    pub payer: Option<String>,
+    pub recent_blockhash: String,
+    pub signatures: Vec<String>,
+    pub account_keys: Vec<String>,
+    pub address_table_lookups: Vec<AddressTableLookupView>,
+    pub instructions: Vec<InstructionView>,
+    pub tip: TipVerdict,
+    pub compute_budget: ComputeBudgetSettings,
+    /// Requested unit limit, or the runtime default when none is set.
+    pub effective_compute_unit_limit: u32,
+    pub priority_fee_lamports: u64,
+}
+
+pub fn inspect(transaction: &VersionedTransaction, decoders: &DecoderRegistry, tip_stage: &TipStage) -> TransactionBreakdown {
+    let message = &transaction.message;
+    let account_keys = message.static_account_keys();
+    let instructions = message.instructions();
+    let num_signers = message.header().num_required_signatures as usize;
+    let tip = TipVerdict::evaluate(tip_stage, account_keys, num_signers, instructions);
+    let compute_budget = ComputeBudgetSettings::from_instructions(account_keys, instructions);
+    TransactionBreakdown {
+        version: match message {
+            VersionedMessage::Legacy(_) => "legacy".to_string(),
+            VersionedMessage::V0(_) => "0".to_string(),
+        },
+        header: *message.header(),
+        payer: account_keys.first().map(|k| k.to_string()),
+        recent_blockhash: message.recent_blockhash().to_string(),
+        signatures: transaction.signatures.iter().map(|s| s.to_string()).collect(),
+        account_keys: account_keys.iter().map(|k| k.to_string()).collect(),
+        address_table_lookups: message
+            .address_table_lookups()
+            .unwrap_or_default()
+            .iter()
+            .map(|lookup| AddressTableLookupView {
+                account_key: lookup.account_key.to_string(),
+                writable_indexes: lookup.writable_indexes.clone(),
+                readonly_indexes: lookup.readonly_indexes.clone(),
+            })
+            .collect(),
+        instructions: decoders.decode_instructions(account_keys, instructions),
+        tip,
+        effective_compute_unit_limit: compute_budget.effective_unit_limit(),
+        priority_fee_lamports: compute_budget.priority_fee_lamports(),
+        compute_budget,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute_budget_policy::ComputeBudgetPolicy;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+
+    fn stage(tip: Pubkey, require_last: bool, credit: bool) -> TipStage {
+        TipStage {
+            tip_pubkeys: vec![tip],
+            min_tip_lamports: 1_000,
+            require_last,
+            compute_budget_policy: ComputeBudgetPolicy {
+                priority_fee_counts_toward_tip: credit,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn transaction(payer: &Pubkey, instructions: &[Instruction]) -> VersionedTransaction {
+        Transaction::new_unsigned(Message::new(instructions, Some(payer))).into()
+    }
+
+    fn verdict(stage: &TipStage, tx: &VersionedTransaction) -> TipVerdict {
+        inspect(tx, &DecoderRegistry::new(), stage).tip
+    }
+
+    #[test]
+    fn verdict_matches_tip_stage() {
+        let payer = Pubkey::new_unique();
+        let tip = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        // Tip followed by another instruction: fine unless the tip must be last.
+        let tx = transaction(
+            &payer,
+            &[system_instruction::transfer(&payer, &tip, 1_000), system_instruction::transfer(&payer, &other, 5)],
+        );
+        assert_eq!(verdict(&stage(tip, false, false), &tx).status, TipStatus::Ok);
+        let not_last = verdict(&stage(tip, true, false), &tx);
+        assert_eq!(not_last.status, TipStatus::NotLast);
+        assert_eq!(not_last.reason, Some(ValidationError::TipNotLast.to_string()));
+
+        // A transfer from an account that does not sign cannot pay the tip.
+        let mut unsigned = system_instruction::transfer(&other, &tip, 1_000);
+        unsigned.accounts[0].is_signer = false;
+        let tx = transaction(&payer, &[unsigned]);
+        assert_eq!(verdict(&stage(tip, false, false), &tx).status, TipStatus::PayerNotSigner);
+
+        // 200_000 units at 4_000 micro-lamports = 800 lamports of priority fee.
+        let tx = transaction(
+            &payer,
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+                ComputeBudgetInstruction::set_compute_unit_price(4_000),
+                system_instruction::transfer(&payer, &tip, 200),
+            ],
+        );
+        assert_eq!(verdict(&stage(tip, false, false), &tx).status, TipStatus::TooLow);
+        let credited = verdict(&stage(tip, false, true), &tx);
+        assert_eq!(credited.status, TipStatus::Ok);
+        assert_eq!(credited.priority_fee_credit_lamports, 800);
+        assert_eq!(credited.found_lamports, Some(1_000));
+        assert_eq!(credited.tip_account, Some(tip.to_string()));
+    }
+}
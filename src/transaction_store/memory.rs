@@ -0,0 +1,276 @@
+use super::{into_page, page_limit, PageCursor, StoreStats, TransactionStore};
+use crate::{
+    errors::ServiceError,
+    models::{DisplayedTransaction, SortOrder, TransactionPage, TransactionQuery},
+};
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct Inner {
+    transactions: HashMap<String, DisplayedTransaction>,
+    /// Resent transactions keep their signature under a new id, so one
+    /// signature can map to several ids.
+    ids_by_signature: HashMap<String, HashSet<String>>,
+    /// Running total of `entry_size` over `transactions`, so `stats` stays cheap.
+    approx_bytes: usize,
+}
+
+impl Inner {
+    fn insert(&mut self, transaction: DisplayedTransaction) {
+        self.remove(&transaction.id);
+        self.approx_bytes += entry_size(&transaction);
+        if let Some(signature) = &transaction.signature {
+            self.ids_by_signature
+                .entry(signature.clone())
+                .or_default()
+                .insert(transaction.id.clone());
+        }
+        self.transactions.insert(transaction.id.clone(), transaction);
+    }
+
+    fn remove(&mut self, id: &str) -> Option<DisplayedTransaction> {
+        let transaction = self.transactions.remove(id)?;
+        if let Some(signature) = &transaction.signature {
+            if let Some(ids) = self.ids_by_signature.get_mut(signature) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.ids_by_signature.remove(signature);
+                }
+            }
+        }
+        self.approx_bytes -= entry_size(&transaction);
+        Some(transaction)
+    }
+
+    /// Every transaction stored under `signature`.
+    fn with_signature<'a>(&'a self, signature: &str) -> impl Iterator<Item = &'a DisplayedTransaction> {
+        self.ids_by_signature
+            .get(signature)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.transactions.get(id))
+    }
+
+    /// Ids ordered oldest first.
+    fn ids_by_age(&self) -> Vec<String> {
+        let mut entries: Vec<(i64, &String)> = self
+            .transactions
+            .values()
+            .map(|t| (t.timestamp.timestamp_micros(), &t.id))
+            .collect();
+        entries.sort();
+        entries.into_iter().map(|(_, id)| id.clone()).collect()
+    }
+}
+
+/// Heap estimate for a stored transaction plus its signature index entry.
+fn entry_size(transaction: &DisplayedTransaction) -> usize {
+    let index = transaction
+        .signature
+        .as_ref()
+        .map(|signature| signature.len() + transaction.id.len())
+        .unwrap_or(0);
+    approx_size(transaction) + index
+}
+
+fn approx_size(transaction: &DisplayedTransaction) -> usize {
+    let strings = [
+        Some(&transaction.id),
+        Some(&transaction.transaction_id),
+        Some(&transaction.from_address),
+        Some(&transaction.to_address),
+        transaction.memo.as_ref(),
+        Some(&transaction.status),
+        transaction.signature.as_ref(),
+        transaction.transaction_data.as_ref(),
+        transaction.tip_account.as_ref(),
+    ];
+    let transfers = transaction.transfers.iter().chain(transaction.tip.iter());
+    std::mem::size_of::<DisplayedTransaction>()
+        + strings.iter().flatten().map(|s| s.capacity()).sum::<usize>()
+        + transfers
+            .map(|t| std::mem::size_of_val(t) + t.source.capacity() + t.destination.capacity())
+            .sum::<usize>()
+        + transaction
+            .token_transfers
+            .iter()
+            .map(|t| {
+                let optional = [&t.source, &t.destination, &t.authority, &t.mint];
+                std::mem::size_of_val(t)
+                    + t.program.capacity()
+                    + t.kind.capacity()
+                    + optional.iter().filter_map(|s| s.as_ref()).map(|s| s.capacity()).sum::<usize>()
+            })
+            .sum::<usize>()
+        + transaction
+            .instructions
+            .iter()
+            .map(|ix| {
+                // Serialized size is a reasonable proxy for the nested JSON values.
+                serde_json::to_vec(ix).map(|v| v.len()).unwrap_or(0)
+            })
+            .sum::<usize>()
+}
+
+#[derive(Default)]
+pub struct InMemoryTransactionStore {
+    inner: Mutex<Inner>,
+}
+
+impl InMemoryTransactionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Inner>, ServiceError> {
+        self.inner.lock()
+            .map_err(|e| ServiceError::Internal(format!("Failed to lock transactions: {}", e)))
+    }
+}
+
+/// Page order: timestamp, then id to break ties.
+fn sort_key(transaction: &DisplayedTransaction) -> (i64, &str) {
+    (transaction.timestamp.timestamp_micros(), transaction.id.as_str())
+}
+
+fn matches(query: &TransactionQuery, transaction: &DisplayedTransaction) -> bool {
+    query.status.as_ref().is_none_or(|s| *s == transaction.status)
+        && query.from_address.as_ref().is_none_or(|a| *a == transaction.from_address)
+        && query.signature.as_ref().is_none_or(|s| transaction.signature.as_ref() == Some(s))
+        && query.tip_account.as_ref().is_none_or(|t| transaction.tip_account.as_ref() == Some(t))
+        && query.since.is_none_or(|since| transaction.timestamp >= since)
+        && query.until.is_none_or(|until| transaction.timestamp < until)
+}
+
+impl TransactionStore for InMemoryTransactionStore {
+    fn insert(&self, transaction: DisplayedTransaction) -> Result<(), ServiceError> {
+        self.lock()?.insert(transaction);
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<DisplayedTransaction>, ServiceError> {
+        Ok(self.lock()?.transactions.get(id).cloned())
+    }
+
+    fn get_by_signature(&self, signature: &str) -> Result<Option<DisplayedTransaction>, ServiceError> {
+        let inner = self.lock()?;
+        Ok(inner.with_signature(signature).max_by(|a, b| sort_key(a).cmp(&sort_key(b))).cloned())
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Result<TransactionPage, ServiceError> {
+        let limit = page_limit(query);
+        let cursor = query.cursor.as_deref().map(PageCursor::decode).transpose()?;
+        let position = cursor.as_ref().map(|c| (c.timestamp_micros, c.id.as_str()));
+        let after_cursor = |t: &DisplayedTransaction| match (position, query.sort) {
+            (None, _) => true,
+            (Some(position), SortOrder::Asc) => sort_key(t) > position,
+            (Some(position), SortOrder::Desc) => sort_key(t) < position,
+        };
+        let order = |a: &&DisplayedTransaction, b: &&DisplayedTransaction| match query.sort {
+            SortOrder::Asc => sort_key(a).cmp(&sort_key(b)),
+            SortOrder::Desc => sort_key(b).cmp(&sort_key(a)),
+        };
+
+        // Filter and order references under the lock; only the page is cloned.
+        let inner = self.lock()?;
+        let mut items: Vec<&DisplayedTransaction> = match &query.signature {
+            Some(signature) => inner.with_signature(signature).collect(),
+            None => inner.transactions.values().collect(),
+        };
+        items.retain(|t| matches(query, t) && after_cursor(t));
+        if items.len() > limit + 1 {
+            items.select_nth_unstable_by(limit, order);
+            items.truncate(limit + 1);
+        }
+        items.sort_unstable_by(order);
+        Ok(into_page(items.into_iter().cloned().collect(), limit))
+    }
+
+    fn evict_older_than(&self, cutoff: DateTime<Utc>) -> Result<Vec<DisplayedTransaction>, ServiceError> {
+        let mut inner = self.lock()?;
+        let expired: Vec<String> = inner
+            .transactions
+            .values()
+            .filter(|t| t.timestamp < cutoff)
+            .map(|t| t.id.clone())
+            .collect();
+        Ok(expired.iter().filter_map(|id| inner.remove(id)).collect())
+    }
+
+    fn evict_over_count(&self, max_count: usize) -> Result<Vec<DisplayedTransaction>, ServiceError> {
+        let mut inner = self.lock()?;
+        let excess = inner.transactions.len().saturating_sub(max_count);
+        if excess == 0 {
+            return Ok(Vec::new());
+        }
+        let oldest: Vec<String> = inner.ids_by_age().into_iter().take(excess).collect();
+        Ok(oldest.iter().filter_map(|id| inner.remove(id)).collect())
+    }
+
+    fn strip_payloads_older_than(&self, cutoff: DateTime<Utc>) -> Result<usize, ServiceError> {
+        let mut inner = self.lock()?;
+        let mut stripped = 0;
+        let mut freed = 0;
+        for transaction in inner.transactions.values_mut() {
+            if transaction.timestamp < cutoff {
+                if let Some(data) = transaction.transaction_data.take() {
+                    freed += data.capacity();
+                    stripped += 1;
+                }
+            }
+        }
+        inner.approx_bytes -= freed;
+        Ok(stripped)
+    }
+
+    fn stats(&self) -> Result<StoreStats, ServiceError> {
+        let inner = self.lock()?;
+        Ok(StoreStats {
+            backend: self.backend_name(),
+            transactions: inner.transactions.len(),
+            approx_bytes: inner.approx_bytes as u64,
+        })
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction_store::tests::sample;
+    use chrono::TimeZone;
+
+    fn recomputed(store: &InMemoryTransactionStore) -> u64 {
+        store.lock().unwrap().transactions.values().map(entry_size).sum::<usize>() as u64
+    }
+
+    #[test]
+    fn byte_count_tracks_every_mutation() {
+        let store = InMemoryTransactionStore::new();
+        for (id, micros) in [("a", 1), ("b", 2), ("c", 3), ("d", 4)] {
+            store.insert(sample(id, micros)).unwrap();
+        }
+        let mut replaced = sample("a", 1);
+        replaced.memo = Some("a much longer memo than before".to_string());
+        replaced.signature = None;
+        store.insert(replaced).unwrap();
+        assert_eq!(store.stats().unwrap().approx_bytes, recomputed(&store));
+
+        store.strip_payloads_older_than(Utc.timestamp_micros(3).unwrap()).unwrap();
+        assert_eq!(store.stats().unwrap().approx_bytes, recomputed(&store));
+
+        store.evict_older_than(Utc.timestamp_micros(2).unwrap()).unwrap();
+        store.evict_over_count(1).unwrap();
+        assert_eq!(store.stats().unwrap().transactions, 1);
+        assert_eq!(store.stats().unwrap().approx_bytes, recomputed(&store));
+
+        store.evict_over_count(0).unwrap();
+        assert_eq!(store.stats().unwrap().approx_bytes, 0);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{
+    errors::ServiceError,
+    models::{DisplayedTransaction, TransactionPage, TransactionQuery},
+};
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+mod memory;
+mod sqlite;
+/// Shared test doubles.
+#[cfg(test)]
+pub mod testing;
+
+pub use memory::InMemoryTransactionStore;
+pub use sqlite::SqliteTransactionStore;
+
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+pub const MAX_PAGE_LIMIT: usize = 500;
+
+/// Persistence for transactions shown by the display service.
+pub trait TransactionStore: Send + Sync {
+    /// Inserts the transaction, replacing any existing entry with the same id.
+    fn insert(&self, transaction: DisplayedTransaction) -> Result<(), ServiceError>;
+
+    fn get(&self, id: &str) -> Result<Option<DisplayedTransaction>, ServiceError>;
+
+    /// Returns the newest transaction with `signature`; resends keep the
+    /// signature under a new id.
+    fn get_by_signature(&self, signature: &str) -> Result<Option<DisplayedTransaction>, ServiceError>;
+
+    /// Returns one page of transactions matching `query`, ordered by timestamp.
+    fn query(&self, query: &TransactionQuery) -> Result<TransactionPage, ServiceError>;
+
+    /// Removes transactions stored before `cutoff` and returns them.
+    fn evict_older_than(&self, cutoff: DateTime<Utc>) -> Result<Vec<DisplayedTransaction>, ServiceError>;
+
+    /// Removes the oldest transactions until at most `max_count` remain and returns them.
+    fn evict_over_count(&self, max_count: usize) -> Result<Vec<DisplayedTransaction>, ServiceError>;
+
+    /// Drops `transaction_data` from transactions stored before `cutoff`, keeping
+    /// their metadata. Returns the number of payloads dropped.
+    fn strip_payloads_older_than(&self, cutoff: DateTime<Utc>) -> Result<usize, ServiceError>;
+
+    fn stats(&self) -> Result<StoreStats, ServiceError>;
+
+    /// Makes every write durable before shutdown. Backends that write through
+    /// need not override it.
+    fn flush(&self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+
+    fn backend_name(&self) -> &'static str;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreStats {
+    pub backend: &'static str,
+    pub transactions: usize,
+    /// Approximate bytes held by the backend (heap estimate or database size).
+    pub approx_bytes: u64,
+}
+
+/// Opens the backend named by `backend` (`memory` or `sqlite`).
+pub fn open_store(backend: &str, path: &str) -> Result<Arc<dyn TransactionStore>, ServiceError> {
+    match backend {
+        "memory" => Ok(Arc::new(InMemoryTransactionStore::new())),
+        "sqlite" => Ok(Arc::new(SqliteTransactionStore::open(path)?)),
+        other => Err(ServiceError::Storage(format!("Unknown storage backend: {}", other))),
+    }
+}
+
+/// Keyset position of the last item on a page: `(timestamp, id)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub timestamp_micros: i64,
+    pub id: String,
+}
+
+impl PageCursor {
+    pub fn after(transaction: &DisplayedTransaction) -> Self {
+        Self {
+            timestamp_micros: transaction.timestamp.timestamp_micros(),
+            id: transaction.id.clone(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(format!("{}:{}", self.timestamp_micros, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, ServiceError> {
+        let invalid = || ServiceError::InvalidQuery(format!("Invalid cursor: {}", cursor));
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| invalid())?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let (timestamp, id) = text.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            timestamp_micros: timestamp.parse().map_err(|_| invalid())?,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Page size requested by `query`, clamped to `1..=MAX_PAGE_LIMIT`.
+pub fn page_limit(query: &TransactionQuery) -> usize {
+    query.limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+}
+
+/// Trims a result set fetched with `limit + 1` rows and computes the next cursor.
+pub fn into_page(mut items: Vec<DisplayedTransaction>, limit: usize) -> TransactionPage {
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|last| PageCursor::after(last).encode())
+    } else {
+        None
+    };
+    TransactionPage { items, next_cursor }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::models::SortOrder;
+    use chrono::TimeZone;
+
+    pub(crate) fn sample(id: &str, timestamp_micros: i64) -> DisplayedTransaction {
+        DisplayedTransaction {
+            id: id.to_string(),
+            transaction_id: id.to_string(),
+            from_address: "payer".to_string(),
+            to_address: "recipient".to_string(),
+            amount: 0.0,
+            memo: None,
+            status: "confirmed".to_string(),
+            timestamp: Utc.timestamp_micros(timestamp_micros).unwrap(),
+            signature: Some(format!("sig-{}", id)),
+            block_time: None,
+            transaction_data: Some("AQID".to_string()),
+            tip_account: None,
+            tip: None,
+            tip_lamports: 0,
+            transfers: Vec::new(),
+            total_lamports: 0,
+            token_transfers: Vec::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    pub(crate) fn backends() -> Vec<Arc<dyn TransactionStore>> {
+        vec![
+            open_store("memory", "").unwrap(),
+            open_store("sqlite", ":memory:").unwrap(),
+        ]
+    }
+
+    /// Follows `next_cursor` until the last page, returning every id seen.
+    fn walk(store: &dyn TransactionStore, sort: SortOrder, limit: usize) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = store
+                .query(&TransactionQuery {
+                    limit: Some(limit),
+                    cursor: cursor.take(),
+                    sort,
+                    ..Default::default()
+                })
+                .unwrap();
+            assert!(page.items.len() <= limit);
+            ids.extend(page.items.into_iter().map(|t| t.id));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return ids,
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        for cursor in [
+            (1_700_000_000_123_456, "3f1c-uuid"),
+            (-5, "id:with:colons"),
+            (0, ""),
+        ] {
+            let cursor = PageCursor {
+                timestamp_micros: cursor.0,
+                id: cursor.1.to_string(),
+            };
+            assert_eq!(PageCursor::decode(&cursor.encode()).unwrap(), cursor);
+        }
+    }
+
+    #[test]
+    fn lookup_by_signature_follows_replacements() {
+        for store in backends() {
+            let backend = store.backend_name();
+            store.insert(sample("a", 10)).unwrap();
+            assert_eq!(store.get_by_signature("sig-a").unwrap().map(|t| t.id), Some("a".to_string()), "{}", backend);
+            assert!(store.get_by_signature("sig-b").unwrap().is_none(), "{}", backend);
+
+            let mut resigned = sample("a", 10);
+            resigned.signature = Some("sig-a2".to_string());
+            store.insert(resigned).unwrap();
+            assert!(store.get_by_signature("sig-a").unwrap().is_none(), "{}", backend);
+            assert_eq!(store.get_by_signature("sig-a2").unwrap().map(|t| t.id), Some("a".to_string()), "{}", backend);
+        }
+    }
+
+    #[test]
+    fn resends_sharing_a_signature_stay_findable() {
+        for store in backends() {
+            let backend = store.backend_name();
+            let mut original = sample("a", 10);
+            original.signature = Some("sig".to_string());
+            let mut resend = sample("b", 20);
+            resend.signature = Some("sig".to_string());
+            store.insert(original.clone()).unwrap();
+            store.insert(resend).unwrap();
+            let query = TransactionQuery {
+                signature: Some("sig".to_string()),
+                ..Default::default()
+            };
+            let ids = |store: &dyn TransactionStore| -> Vec<String> {
+                store.query(&query).unwrap().items.into_iter().map(|t| t.id).collect()
+            };
+            assert_eq!(ids(store.as_ref()), ["b", "a"], "{}", backend);
+            assert_eq!(store.get_by_signature("sig").unwrap().map(|t| t.id), Some("b".to_string()), "{}", backend);
+
+            // Replacing, stripping and evicting the older copy leave the resend indexed.
+            original.signature = None;
+            store.insert(original.clone()).unwrap();
+            original.signature = Some("sig".to_string());
+            store.insert(original).unwrap();
+            store.strip_payloads_older_than(Utc.timestamp_micros(15).unwrap()).unwrap();
+            store.evict_older_than(Utc.timestamp_micros(15).unwrap()).unwrap();
+            assert_eq!(ids(store.as_ref()), ["b"], "{}", backend);
+            assert_eq!(store.get_by_signature("sig").unwrap().map(|t| t.id), Some("b".to_string()), "{}", backend);
+        }
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let no_separator = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("12345");
+        let bad_timestamp = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("soon:abc");
+        for cursor in ["not base64!", no_separator.as_str(), bad_timestamp.as_str()] {
+            let result = PageCursor::decode(cursor);
+            assert!(matches!(result, Err(ServiceError::InvalidQuery(_))), "{}", cursor);
+        }
+    }
+
+    #[test]
+    fn pages_break_timestamp_ties_by_id_in_both_orders() {
+        // Three transactions share a timestamp, so only the id keeps pages apart.
+        let rows = [("c", 20), ("a", 20), ("e", 10), ("b", 20), ("d", 30)];
+        let ascending = ["e", "a", "b", "c", "d"];
+        for store in backends() {
+            for (id, micros) in rows {
+                store.insert(sample(id, micros)).unwrap();
+            }
+            let mut descending = ascending;
+            descending.reverse();
+            for limit in [1, 2, 3, 10] {
+                let backend = store.backend_name();
+                assert_eq!(walk(store.as_ref(), SortOrder::Asc, limit), ascending, "{} asc/{}", backend, limit);
+                assert_eq!(walk(store.as_ref(), SortOrder::Desc, limit), descending, "{} desc/{}", backend, limit);
+            }
+        }
+    }
+
+    #[test]
+    fn last_full_page_has_no_cursor() {
+        for store in backends() {
+            for (id, micros) in [("a", 1), ("b", 2)] {
+                store.insert(sample(id, micros)).unwrap();
+            }
+            let query = TransactionQuery {
+                limit: Some(2),
+                ..Default::default()
+            };
+            let page = store.query(&query).unwrap();
+            assert_eq!(page.items.len(), 2);
+            assert_eq!(page.next_cursor, None, "{}", store.backend_name());
+        }
+    }
+}
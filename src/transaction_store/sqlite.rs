@@ -0,0 +1,295 @@
+use super::{into_page, page_limit, PageCursor, StoreStats, TransactionStore};
+use crate::{
+    errors::ServiceError,
+    models::{DisplayedTransaction, SortOrder, TransactionPage, TransactionQuery},
+};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension, Row};
+use std::sync::Mutex;
+use tracing::info;
+
+/// Schema migrations, applied in order and tracked with `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE IF NOT EXISTS transactions (
+        id               TEXT PRIMARY KEY,
+        transaction_id   TEXT NOT NULL,
+        from_address     TEXT NOT NULL,
+        to_address       TEXT NOT NULL,
+        amount           REAL NOT NULL,
+        memo             TEXT,
+        status           TEXT NOT NULL,
+        timestamp        INTEGER NOT NULL,
+        signature        TEXT,
+        block_time       INTEGER,
+        transaction_data TEXT
+    );
+    CREATE INDEX IF NOT EXISTS idx_transactions_signature ON transactions(signature);
+    CREATE INDEX IF NOT EXISTS idx_transactions_from_address ON transactions(from_address);
+    CREATE INDEX IF NOT EXISTS idx_transactions_timestamp ON transactions(timestamp);
+", "
+    ALTER TABLE transactions ADD COLUMN tip_account TEXT;
+    CREATE INDEX IF NOT EXISTS idx_transactions_tip_account ON transactions(tip_account);
+    CREATE INDEX IF NOT EXISTS idx_transactions_status ON transactions(status);
+", "
+    ALTER TABLE transactions ADD COLUMN tip TEXT;
+    ALTER TABLE transactions ADD COLUMN transfers TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE transactions ADD COLUMN total_lamports INTEGER NOT NULL DEFAULT 0;
+", "
+    ALTER TABLE transactions ADD COLUMN token_transfers TEXT NOT NULL DEFAULT '[]';
+", "
+    ALTER TABLE transactions ADD COLUMN instructions TEXT NOT NULL DEFAULT '[]';
+", "
+    ALTER TABLE transactions ADD COLUMN tip_lamports INTEGER NOT NULL DEFAULT 0;
+"];
+
+const COLUMNS: &str = "id, transaction_id, from_address, to_address, amount, memo, status, \
+                       timestamp, signature, block_time, transaction_data, tip_account, \
+                       tip, transfers, total_lamports, token_transfers, instructions, \
+                       tip_lamports";
+
+pub struct SqliteTransactionStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteTransactionStore {
+    pub fn open(path: &str) -> Result<Self, ServiceError> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+        migrate(&conn)?;
+        info!("Opened SQLite transaction store at {}", path);
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>, ServiceError> {
+        self.conn.lock()
+            .map_err(|e| ServiceError::Internal(format!("Failed to lock transaction store: {}", e)))
+    }
+}
+
+fn migrate(conn: &Connection) -> Result<(), ServiceError> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            migration,
+            index + 1
+        ))?;
+        info!("Applied transaction store migration {}", index + 1);
+    }
+    Ok(())
+}
+
+fn row_to_transaction(row: &Row<'_>) -> rusqlite::Result<DisplayedTransaction> {
+    let timestamp_micros: i64 = row.get(7)?;
+    let timestamp = DateTime::from_timestamp_micros(timestamp_micros).ok_or_else(|| {
+        rusqlite::Error::IntegralValueOutOfRange(7, timestamp_micros)
+    })?;
+    Ok(DisplayedTransaction {
+        id: row.get(0)?,
+        transaction_id: row.get(1)?,
+        from_address: row.get(2)?,
+        to_address: row.get(3)?,
+        amount: row.get(4)?,
+        memo: row.get(5)?,
+        status: row.get(6)?,
+        timestamp,
+        signature: row.get(8)?,
+        block_time: row.get(9)?,
+        transaction_data: row.get(10)?,
+        tip_account: row.get(11)?,
+        tip: json_column(row, 12)?,
+        transfers: json_column(row, 13)?,
+        total_lamports: row.get::<_, i64>(14)? as u64,
+        token_transfers: json_column(row, 15)?,
+        instructions: json_column(row, 16)?,
+        tip_lamports: row.get::<_, i64>(17)? as u64,
+    })
+}
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row<'_>, index: usize) -> rusqlite::Result<T> {
+    let text: Option<String> = row.get(index)?;
+    serde_json::from_str(text.as_deref().unwrap_or("null")).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, ServiceError> {
+    serde_json::to_string(value)
+        .map_err(|e| ServiceError::Internal(format!("Failed to serialize column: {}", e)))
+}
+
+impl TransactionStore for SqliteTransactionStore {
+    fn insert(&self, transaction: DisplayedTransaction) -> Result<(), ServiceError> {
+        let conn = self.lock()?;
+        conn.execute(
+            &format!("INSERT OR REPLACE INTO transactions ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)", COLUMNS),
+            params![
+                transaction.id,
+                transaction.transaction_id,
+                transaction.from_address,
+                transaction.to_address,
+                transaction.amount,
+                transaction.memo,
+                transaction.status,
+                transaction.timestamp.timestamp_micros(),
+                transaction.signature,
+                transaction.block_time,
+                transaction.transaction_data,
+                transaction.tip_account,
+                transaction.tip.as_ref().map(to_json).transpose()?,
+                to_json(&transaction.transfers)?,
+                transaction.total_lamports as i64,
+                to_json(&transaction.token_transfers)?,
+                to_json(&transaction.instructions)?,
+                transaction.tip_lamports as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<DisplayedTransaction>, ServiceError> {
+        let conn = self.lock()?;
+        let transaction = conn
+            .query_row(
+                &format!("SELECT {} FROM transactions WHERE id = ?1", COLUMNS),
+                params![id],
+                row_to_transaction,
+            )
+            .optional()?;
+        Ok(transaction)
+    }
+
+    fn get_by_signature(&self, signature: &str) -> Result<Option<DisplayedTransaction>, ServiceError> {
+        let conn = self.lock()?;
+        let transaction = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM transactions WHERE signature = ?1
+                     ORDER BY timestamp DESC, id DESC LIMIT 1",
+                    COLUMNS
+                ),
+                params![signature],
+                row_to_transaction,
+            )
+            .optional()?;
+        Ok(transaction)
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Result<TransactionPage, ServiceError> {
+        let limit = page_limit(query);
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let text_filters = [
+            ("status = ?", &query.status),
+            ("from_address = ?", &query.from_address),
+            ("signature = ?", &query.signature),
+            ("tip_account = ?", &query.tip_account),
+        ];
+        for (condition, filter) in text_filters {
+            if let Some(value) = filter {
+                conditions.push(condition);
+                values.push(Value::Text(value.clone()));
+            }
+        }
+        if let Some(since) = query.since {
+            conditions.push("timestamp >= ?");
+            values.push(Value::Integer(since.timestamp_micros()));
+        }
+        if let Some(until) = query.until {
+            conditions.push("timestamp < ?");
+            values.push(Value::Integer(until.timestamp_micros()));
+        }
+        let (order, keyset) = match query.sort {
+            SortOrder::Asc => ("ASC", "(timestamp, id) > (?, ?)"),
+            SortOrder::Desc => ("DESC", "(timestamp, id) < (?, ?)"),
+        };
+        if let Some(cursor) = query.cursor.as_deref() {
+            let cursor = PageCursor::decode(cursor)?;
+            conditions.push(keyset);
+            values.push(Value::Integer(cursor.timestamp_micros));
+            values.push(Value::Text(cursor.id));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        values.push(Value::Integer((limit + 1) as i64));
+        let sql = format!(
+            "SELECT {} FROM transactions {} ORDER BY timestamp {order}, id {order} LIMIT ?",
+            COLUMNS, where_clause
+        );
+
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt
+            .query_map(params_from_iter(values), row_to_transaction)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(into_page(items, limit))
+    }
+
+    fn evict_older_than(&self, cutoff: DateTime<Utc>) -> Result<Vec<DisplayedTransaction>, ServiceError> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "DELETE FROM transactions WHERE timestamp < ?1 RETURNING {}",
+            COLUMNS
+        ))?;
+        let evicted = stmt
+            .query_map(params![cutoff.timestamp_micros()], row_to_transaction)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(evicted)
+    }
+
+    fn evict_over_count(&self, max_count: usize) -> Result<Vec<DisplayedTransaction>, ServiceError> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(&format!(
+            "DELETE FROM transactions WHERE id IN (
+                SELECT id FROM transactions ORDER BY timestamp DESC, id DESC LIMIT -1 OFFSET ?1
+            ) RETURNING {}",
+            COLUMNS
+        ))?;
+        let evicted = stmt
+            .query_map(params![max_count as i64], row_to_transaction)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(evicted)
+    }
+
+    fn strip_payloads_older_than(&self, cutoff: DateTime<Utc>) -> Result<usize, ServiceError> {
+        let conn = self.lock()?;
+        let stripped = conn.execute(
+            "UPDATE transactions SET transaction_data = NULL
+             WHERE timestamp < ?1 AND transaction_data IS NOT NULL",
+            params![cutoff.timestamp_micros()],
+        )?;
+        Ok(stripped)
+    }
+
+    fn stats(&self) -> Result<StoreStats, ServiceError> {
+        let conn = self.lock()?;
+        let transactions: i64 = conn.query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))?;
+        let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(StoreStats {
+            backend: self.backend_name(),
+            transactions: transactions as usize,
+            approx_bytes: (page_count * page_size) as u64,
+        })
+    }
+
+    /// Checkpoints the WAL into the main database file and truncates it.
+    fn flush(&self) -> Result<(), ServiceError> {
+        let conn = self.lock()?;
+        let busy: i64 = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+        if busy != 0 {
+            return Err(ServiceError::Storage("WAL checkpoint blocked by another connection".to_string()));
+        }
+        Ok(())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "sqlite"
+    }
+}
@@ -0,0 +1,84 @@
+use super::{StoreStats, TransactionStore};
+use crate::{
+    errors::ServiceError,
+    models::{DisplayedTransaction, TransactionPage, TransactionQuery},
+};
+
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Test double that forwards every call to `inner`, first passing the method
+/// name to `intercept`. Returning an error fails that call instead; returning
+/// `None` lets it through. `intercept` may also count calls, or panic on calls
+/// that must not happen.
+pub struct InterceptingStore<F> {
+    inner: Arc<dyn TransactionStore>,
+    intercept: F,
+}
+
+impl<F> InterceptingStore<F>
+where
+    F: Fn(&str) -> Option<ServiceError> + Send + Sync,
+{
+    pub fn new(inner: Arc<dyn TransactionStore>, intercept: F) -> Self {
+        Self { inner, intercept }
+    }
+
+    fn check(&self, method: &str) -> Result<(), ServiceError> {
+        (self.intercept)(method).map_or(Ok(()), Err)
+    }
+}
+
+impl<F> TransactionStore for InterceptingStore<F>
+where
+    F: Fn(&str) -> Option<ServiceError> + Send + Sync,
+{
+    fn insert(&self, transaction: DisplayedTransaction) -> Result<(), ServiceError> {
+        self.check("insert")?;
+        self.inner.insert(transaction)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<DisplayedTransaction>, ServiceError> {
+        self.check("get")?;
+        self.inner.get(id)
+    }
+
+    fn get_by_signature(&self, signature: &str) -> Result<Option<DisplayedTransaction>, ServiceError> {
+        self.check("get_by_signature")?;
+        self.inner.get_by_signature(signature)
+    }
+
+    fn query(&self, query: &TransactionQuery) -> Result<TransactionPage, ServiceError> {
+        self.check("query")?;
+        self.inner.query(query)
+    }
+
+    fn evict_older_than(&self, cutoff: DateTime<Utc>) -> Result<Vec<DisplayedTransaction>, ServiceError> {
+        self.check("evict_older_than")?;
+        self.inner.evict_older_than(cutoff)
+    }
+
+    fn evict_over_count(&self, max_count: usize) -> Result<Vec<DisplayedTransaction>, ServiceError> {
+        self.check("evict_over_count")?;
+        self.inner.evict_over_count(max_count)
+    }
+
+    fn strip_payloads_older_than(&self, cutoff: DateTime<Utc>) -> Result<usize, ServiceError> {
+        self.check("strip_payloads_older_than")?;
+        self.inner.strip_payloads_older_than(cutoff)
+    }
+
+    fn stats(&self) -> Result<StoreStats, ServiceError> {
+        self.check("stats")?;
+        self.inner.stats()
+    }
+
+    fn flush(&self) -> Result<(), ServiceError> {
+        self.check("flush")?;
+        self.inner.flush()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.inner.backend_name()
+    }
+}
@@ -0,0 +1,813 @@
+use crate::access_policy::{AccessPolicy, PolicyViolation};
+use crate::compute_budget_policy::{ComputeBudgetPolicy, ComputeBudgetViolation};
+use crate::decoders::compute_budget::ComputeBudgetSettings;
+use crate::rpc_pool::RpcPool;
+use crate::transaction_analyzer;
+
+use axum::http::StatusCode;
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sanitize::Sanitize;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{error, warn};
+
+#[derive(Error, Debug)]
+pub enum ValidationError {
+    #[error("Invalid params: {0}")]
+    InvalidEncoding(String),
+
+    #[error("Invalid transaction format: {0}")]
+    InvalidTransaction(String),
+
+    #[error("Transaction too large: {size} bytes, maximum {max}")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Blockhash not found or expired: {0}")]
+    BlockhashNotFound(String),
+
+    #[error("Tip too low: required >= {required} lamports (~{} SOL), found {found}", lamports_to_sol(*.required))]
+    TipTooLow { required: u64, found: u64 },
+
+    #[error("Missing required tip transfer to configured account")]
+    MissingTip { required: u64 },
+
+    #[error("Tip transfer from {0} is not signed by its funding authority")]
+    TipPayerNotSigner(String),
+
+    #[error("Tip transfer must be the last instruction")]
+    TipNotLast,
+
+    #[error(transparent)]
+    ComputeBudget(#[from] ComputeBudgetViolation),
+
+    #[error("Rejected by policy: {0}")]
+    Policy(#[from] PolicyViolation),
+
+    #[error("Fee payer {payer} balance {balance} lamports is below the {required} lamports this transaction needs (short {})", .required.saturating_sub(*.balance))]
+    InsufficientBalance { payer: String, balance: u64, required: u64 },
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl ValidationError {
+    /// JSON-RPC error code for `/rpc` responses.
+    pub fn code(&self) -> i64 {
+        match self {
+            ValidationError::InvalidEncoding(_) | ValidationError::InvalidTransaction(_) => -32602,
+            ValidationError::TooLarge { .. } => -32005,
+            ValidationError::InvalidSignature(_) => -32006,
+            ValidationError::BlockhashNotFound(_) => -32007,
+            ValidationError::TipTooLow { .. } => -32000,
+            ValidationError::MissingTip { .. } => -32001,
+            ValidationError::TipPayerNotSigner(_) => -32008,
+            ValidationError::TipNotLast => -32009,
+            ValidationError::ComputeBudget(violation) => violation.code(),
+            ValidationError::Policy(violation) => violation.code(),
+            ValidationError::InsufficientBalance { .. } => -32011,
+            ValidationError::Internal(_) => -32603,
+        }
+    }
+
+    /// Machine readable reason returned alongside REST errors.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidEncoding(_) => "invalid_encoding",
+            ValidationError::InvalidTransaction(_) => "invalid_transaction",
+            ValidationError::TooLarge { .. } => "transaction_too_large",
+            ValidationError::InvalidSignature(_) => "invalid_signature",
+            ValidationError::BlockhashNotFound(_) => "blockhash_not_found",
+            ValidationError::TipTooLow { .. } => "tip_too_low",
+            ValidationError::MissingTip { .. } => "tip_missing",
+            ValidationError::TipPayerNotSigner(_) => "tip_payer_not_signer",
+            ValidationError::TipNotLast => "tip_not_last",
+            ValidationError::ComputeBudget(_) => "compute_budget",
+            ValidationError::Policy(_) => "policy",
+            ValidationError::InsufficientBalance { .. } => "insufficient_balance",
+            ValidationError::Internal(_) => "internal",
+        }
+    }
+
+    /// Structured data for errors a client can act on, e.g. how much to tip.
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            ValidationError::TooLarge { size, max } => Some(json!({"size_bytes": size, "max_bytes": max})),
+            ValidationError::TipTooLow { required, found } => Some(json!({
+                "required_lamports": required,
+                "found_lamports": found,
+            })),
+            ValidationError::MissingTip { required } => Some(json!({
+                "required_lamports": required,
+                "found_lamports": 0,
+            })),
+            ValidationError::InsufficientBalance { payer, balance, required } => Some(json!({
+                "payer": payer,
+                "balance_lamports": balance,
+                "required_lamports": required,
+                "shortfall_lamports": required.saturating_sub(*balance),
+            })),
+            _ => None,
+        }
+    }
+
+    /// HTTP status for REST responses.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ValidationError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ValidationError::Policy(_) => StatusCode::FORBIDDEN,
+            ValidationError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A transaction moving through the pipeline. The decode stage fills in
+/// `bytes` and `transaction` for the stages after it.
+pub struct TransactionCandidate {
+    pub encoded: String,
+    pub bytes: Vec<u8>,
+    pub transaction: Option<Transaction>,
+}
+
+impl TransactionCandidate {
+    pub fn new(encoded: &str) -> Self {
+        Self {
+            encoded: encoded.to_string(),
+            bytes: Vec::new(),
+            transaction: None,
+        }
+    }
+
+    pub fn transaction(&self) -> Result<&Transaction, ValidationError> {
+        self.transaction
+            .as_ref()
+            .ok_or_else(|| ValidationError::Internal("transaction not decoded before this stage".to_string()))
+    }
+}
+
+/// One step of the validation pipeline.
+pub trait TransactionValidator: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError>;
+}
+
+/// Runs validators in order, stopping at the first failure.
+pub struct ValidationPipeline {
+    stages: Vec<Box<dyn TransactionValidator>>,
+}
+
+impl ValidationPipeline {
+    pub fn new(stages: Vec<Box<dyn TransactionValidator>>) -> Self {
+        Self { stages }
+    }
+
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    /// Validates a base64 wire transaction. Stages may call RPC, so run this off
+    /// the async runtime.
+    pub fn run(&self, encoded: &str) -> Result<Transaction, ValidationError> {
+        let mut candidate = TransactionCandidate::new(encoded);
+        for stage in &self.stages {
+            let _span = tracing::info_span!("validate", stage = stage.name()).entered();
+            if let Err(e) = stage.validate(&mut candidate) {
+                error!("Validation failed at {} stage: {}", stage.name(), e);
+                return Err(e);
+            }
+        }
+        candidate
+            .transaction
+            .ok_or_else(|| ValidationError::Internal("pipeline has no decode stage".to_string()))
+    }
+}
+
+/// Decodes base64 into a legacy transaction and sanitizes it, so later stages
+/// can rely on every account and program index being in range.
+pub struct DecodeStage;
+
+impl TransactionValidator for DecodeStage {
+    fn name(&self) -> &'static str {
+        "decode"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        candidate.bytes = base64::engine::general_purpose::STANDARD
+            .decode(&candidate.encoded)
+            .map_err(|e| ValidationError::InvalidEncoding(format!("Invalid base64: {}", e)))?;
+        let transaction = bincode::deserialize::<Transaction>(&candidate.bytes)
+            .map_err(|e| ValidationError::InvalidTransaction(e.to_string()))?;
+        transaction
+            .sanitize()
+            .map_err(|e| ValidationError::InvalidTransaction(format!("Malformed message: {}", e)))?;
+        candidate.transaction = Some(transaction);
+        Ok(())
+    }
+}
+
+/// Rejects transactions larger than a packet.
+pub struct SizeStage {
+    pub max_bytes: usize,
+}
+
+impl TransactionValidator for SizeStage {
+    fn name(&self) -> &'static str {
+        "size"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        if candidate.bytes.len() > self.max_bytes {
+            return Err(ValidationError::TooLarge {
+                size: candidate.bytes.len(),
+                max: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Checks the signature count against the header and, when `verify` is set,
+/// verifies every signature.
+pub struct SignatureStage {
+    pub verify: bool,
+}
+
+impl TransactionValidator for SignatureStage {
+    fn name(&self) -> &'static str {
+        "signature"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        let tx = candidate.transaction()?;
+        let required = tx.message.header.num_required_signatures as usize;
+        if tx.signatures.len() != required {
+            return Err(ValidationError::InvalidSignature(format!(
+                "expected {} signatures, found {}",
+                required,
+                tx.signatures.len()
+            )));
+        }
+        if self.verify {
+            tx.verify()
+                .map_err(|e| ValidationError::InvalidSignature(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Asks RPC whether the recent blockhash is still valid. Valid blockhashes are
+/// cached for `ttl`. RPC failures are logged and let the transaction through;
+/// preflight simulation catches stale blockhashes in that case.
+pub struct BlockhashStage {
+    rpc_pool: Arc<RpcPool>,
+    ttl: Duration,
+    valid: Mutex<HashMap<Hash, Instant>>,
+}
+
+impl BlockhashStage {
+    pub fn new(rpc_pool: Arc<RpcPool>, ttl: Duration) -> Self {
+        Self {
+            rpc_pool,
+            ttl,
+            valid: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl TransactionValidator for BlockhashStage {
+    fn name(&self) -> &'static str {
+        "blockhash"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        let blockhash = candidate.transaction()?.message.recent_blockhash;
+        {
+            let mut valid = self.valid.lock().expect("blockhash cache mutex poisoned");
+            valid.retain(|_, checked_at| checked_at.elapsed() < self.ttl);
+            if valid.contains_key(&blockhash) {
+                return Ok(());
+            }
+        }
+        let (endpoint, result) = self.rpc_pool.call("isBlockhashValid", |client| {
+            client.is_blockhash_valid(&blockhash, CommitmentConfig::processed()).map_err(Box::new)
+        });
+        match result {
+            Ok(true) => {
+                self.valid
+                    .lock()
+                    .expect("blockhash cache mutex poisoned")
+                    .insert(blockhash, Instant::now());
+                Ok(())
+            }
+            Ok(false) => Err(ValidationError::BlockhashNotFound(blockhash.to_string())),
+            Err(e) => {
+                warn!("Blockhash check via {} failed, skipping: {}", endpoint, e);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Requires signed System transfers to tip accounts totalling at least
+/// `min_tip_lamports`.
+#[derive(Clone)]
+pub struct TipStage {
+    pub tip_pubkeys: Vec<Pubkey>,
+    pub min_tip_lamports: u64,
+    /// Require the last instruction to be a tip transfer.
+    pub require_last: bool,
+    /// Supplies the priority fee credit when the policy counts it toward the tip.
+    pub compute_budget_policy: ComputeBudgetPolicy,
+}
+
+/// Outcome of [`TipStage::check`].
+#[derive(Debug)]
+pub struct TipCheck {
+    /// Signed tip transfers plus the priority fee credit.
+    pub found_lamports: u64,
+    pub priority_fee_credit: u64,
+    /// Destination of the first signed tip transfer.
+    pub tip_account: Option<String>,
+    pub result: Result<(), ValidationError>,
+}
+
+impl TipStage {
+    /// Applies the tip requirement to a message's account keys (static keys for
+    /// v0) and instructions. `/decode` reports this same result.
+    pub fn check(&self, account_keys: &[Pubkey], num_signers: usize, instructions: &[CompiledInstruction]) -> TipCheck {
+        let budget = ComputeBudgetSettings::from_instructions(account_keys, instructions);
+        let credit = self.compute_budget_policy.tip_credit(&budget);
+        let analysis =
+            match transaction_analyzer::analyze_instructions(account_keys, num_signers, instructions, &self.tip_pubkeys) {
+                Ok(analysis) => analysis,
+                Err(e) => {
+                    return TipCheck {
+                        found_lamports: credit,
+                        priority_fee_credit: credit,
+                        tip_account: None,
+                        result: Err(ValidationError::InvalidTransaction(e.to_string())),
+                    };
+                }
+            };
+        let found = analysis.tip_lamports.saturating_add(credit);
+        let result = if let Some(unsigned) = analysis.unsigned_tips.first() {
+            Err(ValidationError::TipPayerNotSigner(unsigned.source.clone()))
+        } else if analysis.tips.is_empty() && found < self.min_tip_lamports {
+            Err(ValidationError::MissingTip { required: self.min_tip_lamports })
+        } else if found < self.min_tip_lamports {
+            Err(ValidationError::TipTooLow { required: self.min_tip_lamports, found })
+        } else if self.require_last && !analysis.tip_is_last {
+            Err(ValidationError::TipNotLast)
+        } else {
+            Ok(())
+        };
+        TipCheck {
+            found_lamports: found,
+            priority_fee_credit: credit,
+            tip_account: analysis.tip.map(|tip| tip.destination),
+            result,
+        }
+    }
+}
+
+impl TransactionValidator for TipStage {
+    fn name(&self) -> &'static str {
+        "tip"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        let tx = candidate.transaction()?;
+        let num_signers = tx.message.header.num_required_signatures as usize;
+        self.check(&tx.message.account_keys, num_signers, &tx.message.instructions).result
+    }
+}
+
+pub struct ComputeBudgetStage {
+    pub policy: ComputeBudgetPolicy,
+}
+
+impl TransactionValidator for ComputeBudgetStage {
+    fn name(&self) -> &'static str {
+        "compute_budget"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        let tx = candidate.transaction()?;
+        let budget = ComputeBudgetSettings::from_instructions(&tx.message.account_keys, &tx.message.instructions);
+        Ok(self.policy.check(&budget)?)
+    }
+}
+
+/// Program and account allow/deny lists. Rejections are written to the audit log.
+pub struct AccessPolicyStage {
+    pub policy: Arc<AccessPolicy>,
+}
+
+impl TransactionValidator for AccessPolicyStage {
+    fn name(&self) -> &'static str {
+        "policy"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        let tx = candidate.transaction()?;
+        if let Err(violation) = self.policy.check(&tx.message) {
+            let signature = tx.signatures.first().map(|s| s.to_string()).unwrap_or_default();
+            self.policy.audit_rejection(&signature, &tx.message, &violation);
+            return Err(violation.into());
+        }
+        Ok(())
+    }
+}
+
+/// Base fee charged per signature.
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Rejects transactions whose fee payer cannot cover its outgoing transfers
+/// (tips included) plus the signature and priority fees. Balances are cached
+/// for `ttl`. If the balance cannot be fetched the failure is logged and the
+/// transaction is let through; the cluster still rejects an unfunded payer.
+pub struct FeePayerBalanceStage {
+    rpc_pool: Arc<RpcPool>,
+    tip_pubkeys: Vec<Pubkey>,
+    ttl: Duration,
+    balances: Mutex<HashMap<Pubkey, (u64, Instant)>>,
+}
+
+impl FeePayerBalanceStage {
+    pub fn new(rpc_pool: Arc<RpcPool>, tip_pubkeys: Vec<Pubkey>, ttl: Duration) -> Self {
+        Self {
+            rpc_pool,
+            tip_pubkeys,
+            ttl,
+            balances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn balance(&self, payer: &Pubkey) -> Option<u64> {
+        {
+            let mut balances = self.balances.lock().expect("balance cache mutex poisoned");
+            balances.retain(|_, (_, fetched_at)| fetched_at.elapsed() < self.ttl);
+            if let Some((balance, _)) = balances.get(payer) {
+                return Some(*balance);
+            }
+        }
+        let (endpoint, result) = self.rpc_pool.call("getBalance", |client| {
+            client.get_balance_with_commitment(payer, CommitmentConfig::processed()).map_err(Box::new)
+        });
+        match result {
+            Ok(response) => {
+                self.balances
+                    .lock()
+                    .expect("balance cache mutex poisoned")
+                    .insert(*payer, (response.value, Instant::now()));
+                Some(response.value)
+            }
+            Err(e) => {
+                warn!("Balance check for {} via {} failed, skipping: {}", payer, endpoint, e);
+                None
+            }
+        }
+    }
+}
+
+impl TransactionValidator for FeePayerBalanceStage {
+    fn name(&self) -> &'static str {
+        "balance"
+    }
+
+    fn validate(&self, candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+        let tx = candidate.transaction()?;
+        let analysis = transaction_analyzer::analyze(tx, &self.tip_pubkeys)
+            .map_err(|e| ValidationError::InvalidTransaction(e.to_string()))?;
+        let payer = analysis.fee_payer.to_string();
+        let outgoing = analysis
+            .transfers
+            .iter()
+            .filter(|t| t.source == payer)
+            .map(|t| t.lamports)
+            .fold(0u64, u64::saturating_add);
+        let budget = ComputeBudgetSettings::from_instructions(&tx.message.account_keys, &tx.message.instructions);
+        let fee = LAMPORTS_PER_SIGNATURE
+            .saturating_mul(tx.signatures.len() as u64)
+            .saturating_add(budget.priority_fee_lamports());
+        let required = outgoing.saturating_add(fee);
+        let Some(balance) = self.balance(&analysis.fee_payer) else {
+            return Ok(());
+        };
+        if balance < required {
+            return Err(ValidationError::InsufficientBalance { payer, balance, required });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::Message;
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use solana_sdk::system_instruction;
+
+    /// Records its name when run and fails if `fail` is set.
+    struct Recording {
+        name: &'static str,
+        fail: bool,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl TransactionValidator for Recording {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn validate(&self, _candidate: &mut TransactionCandidate) -> Result<(), ValidationError> {
+            self.log.lock().unwrap().push(self.name);
+            if self.fail {
+                return Err(ValidationError::InvalidSignature(self.name.to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    fn signed_transfer(payer: &Keypair) -> Transaction {
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        Transaction::new(&[payer], Message::new(&[transfer], Some(&payer.pubkey())), Hash::new_unique())
+    }
+
+    fn encode(transaction: &Transaction) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+    }
+
+    fn decoded(transaction: &Transaction) -> TransactionCandidate {
+        let mut candidate = TransactionCandidate::new(&encode(transaction));
+        DecodeStage.validate(&mut candidate).unwrap();
+        candidate
+    }
+
+    #[test]
+    fn pipeline_stops_at_the_first_failure_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stage = |name, fail| -> Box<dyn TransactionValidator> {
+            Box::new(Recording { name, fail, log: log.clone() })
+        };
+        let pipeline = ValidationPipeline::new(vec![
+            Box::new(DecodeStage),
+            stage("first", false),
+            stage("second", true),
+            stage("third", true),
+        ]);
+        assert_eq!(pipeline.stage_names(), vec!["decode", "first", "second", "third"]);
+
+        let err = pipeline.run(&encode(&signed_transfer(&Keypair::new()))).unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidSignature(ref stage) if stage == "second"));
+        assert_eq!(*log.lock().unwrap(), vec!["first", "second"]);
+
+        // A failing decode stops the pipeline before any other stage.
+        log.lock().unwrap().clear();
+        assert!(matches!(pipeline.run("not base64!"), Err(ValidationError::InvalidEncoding(_))));
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pipeline_without_decode_stage_is_an_internal_error() {
+        let empty = ValidationPipeline::new(vec![]);
+        let err = empty.run(&encode(&signed_transfer(&Keypair::new()))).unwrap_err();
+        assert!(matches!(err, ValidationError::Internal(ref msg) if msg == "pipeline has no decode stage"));
+
+        // Stages that need the transaction fail the same way when nothing decoded it.
+        let signature_only = ValidationPipeline::new(vec![Box::new(SignatureStage { verify: false })]);
+        let err = signature_only.run(&encode(&signed_transfer(&Keypair::new()))).unwrap_err();
+        assert!(matches!(err, ValidationError::Internal(_)));
+        assert_eq!(err.code(), -32603);
+    }
+
+    #[test]
+    fn error_codes_statuses_and_details() {
+        let payer = Pubkey::new_unique().to_string();
+        let cases = [
+            (ValidationError::InvalidEncoding("x".into()), -32602, StatusCode::BAD_REQUEST, None),
+            (ValidationError::InvalidTransaction("x".into()), -32602, StatusCode::BAD_REQUEST, None),
+            (
+                ValidationError::TooLarge { size: 1300, max: 1232 },
+                -32005,
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Some(json!({"size_bytes": 1300, "max_bytes": 1232})),
+            ),
+            (ValidationError::InvalidSignature("x".into()), -32006, StatusCode::BAD_REQUEST, None),
+            (ValidationError::BlockhashNotFound("x".into()), -32007, StatusCode::BAD_REQUEST, None),
+            (
+                ValidationError::TipTooLow { required: 1000, found: 400 },
+                -32000,
+                StatusCode::BAD_REQUEST,
+                Some(json!({"required_lamports": 1000, "found_lamports": 400})),
+            ),
+            (
+                ValidationError::MissingTip { required: 1000 },
+                -32001,
+                StatusCode::BAD_REQUEST,
+                Some(json!({"required_lamports": 1000, "found_lamports": 0})),
+            ),
+            (ValidationError::TipPayerNotSigner("x".into()), -32008, StatusCode::BAD_REQUEST, None),
+            (ValidationError::TipNotLast, -32009, StatusCode::BAD_REQUEST, None),
+            (
+                ComputeBudgetViolation::UnitPriceTooLow { required: 10, found: 1 }.into(),
+                -32002,
+                StatusCode::BAD_REQUEST,
+                None,
+            ),
+            (
+                ComputeBudgetViolation::UnitLimitTooHigh { max: 10, requested: 20 }.into(),
+                -32003,
+                StatusCode::BAD_REQUEST,
+                None,
+            ),
+            (ComputeBudgetViolation::DuplicateInstruction("x".into()).into(), -32004, StatusCode::BAD_REQUEST, None),
+            (
+                PolicyViolation::Denied { kind: "program", pubkey: payer.clone() }.into(),
+                -32010,
+                StatusCode::FORBIDDEN,
+                None,
+            ),
+            (
+                PolicyViolation::NotAllowed { kind: "account", pubkey: payer.clone() }.into(),
+                -32010,
+                StatusCode::FORBIDDEN,
+                None,
+            ),
+            (
+                ValidationError::InsufficientBalance { payer: payer.clone(), balance: 4_000, required: 15_000 },
+                -32011,
+                StatusCode::BAD_REQUEST,
+                Some(json!({
+                    "payer": payer,
+                    "balance_lamports": 4_000,
+                    "required_lamports": 15_000,
+                    "shortfall_lamports": 11_000,
+                })),
+            ),
+            (ValidationError::Internal("x".into()), -32603, StatusCode::INTERNAL_SERVER_ERROR, None),
+        ];
+        for (error, code, status, details) in cases {
+            assert_eq!(error.code(), code, "{}", error.reason());
+            assert_eq!(error.status_code(), status, "{}", error.reason());
+            assert_eq!(error.details(), details, "{}", error.reason());
+        }
+    }
+
+    #[test]
+    fn decode_stage_rejects_bad_base64_and_unsanitized_messages() {
+        let mut candidate = TransactionCandidate::new("%%%");
+        assert!(matches!(DecodeStage.validate(&mut candidate), Err(ValidationError::InvalidEncoding(_))));
+
+        let mut candidate = TransactionCandidate::new(&base64::engine::general_purpose::STANDARD.encode([1, 2, 3]));
+        assert!(matches!(DecodeStage.validate(&mut candidate), Err(ValidationError::InvalidTransaction(_))));
+        assert!(candidate.transaction.is_none());
+
+        // Deserializes fine, but the program index points past the account keys.
+        let mut transaction = signed_transfer(&Keypair::new());
+        transaction.message.instructions[0].program_id_index = 42;
+        let mut candidate = TransactionCandidate::new(&encode(&transaction));
+        let err = DecodeStage.validate(&mut candidate).unwrap_err();
+        assert!(matches!(err, ValidationError::InvalidTransaction(ref msg) if msg.starts_with("Malformed message")));
+        assert!(candidate.transaction.is_none());
+
+        let transaction = signed_transfer(&Keypair::new());
+        let candidate = decoded(&transaction);
+        assert_eq!(candidate.transaction, Some(transaction));
+    }
+
+    #[test]
+    fn size_stage_rejects_oversized_transactions() {
+        let mut candidate = decoded(&signed_transfer(&Keypair::new()));
+        let size = candidate.bytes.len();
+        assert!(SizeStage { max_bytes: size }.validate(&mut candidate).is_ok());
+        assert!(matches!(
+            SizeStage { max_bytes: size - 1 }.validate(&mut candidate),
+            Err(ValidationError::TooLarge { size: s, max }) if s == size && max == size - 1
+        ));
+    }
+
+    #[test]
+    fn signature_stage_rejects_missing_and_forged_signatures() {
+        let transaction = signed_transfer(&Keypair::new());
+        assert!(SignatureStage { verify: true }.validate(&mut decoded(&transaction)).is_ok());
+
+        // Sanitizing already rejects this, so hand the stage the transaction directly.
+        let mut missing = TransactionCandidate::new("");
+        missing.transaction = Some(Transaction { signatures: vec![], ..transaction.clone() });
+        for verify in [false, true] {
+            let err = SignatureStage { verify }.validate(&mut missing).unwrap_err();
+            assert_eq!(err.to_string(), "Invalid signature: expected 1 signatures, found 0");
+        }
+
+        // The count is right, so only verification catches a forged signature.
+        let mut forged = transaction;
+        forged.signatures[0] = Signature::new_unique();
+        assert!(SignatureStage { verify: false }.validate(&mut decoded(&forged)).is_ok());
+        assert!(matches!(
+            SignatureStage { verify: true }.validate(&mut decoded(&forged)),
+            Err(ValidationError::InvalidSignature(_))
+        ));
+    }
+
+    /// Serves `getBalance` with a fixed balance and counts the requests.
+    fn balance_rpc(lamports: u64) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        let url = crate::test_http::serve(move |body| {
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            // The client asks for the node version once before its first commitment-aware call.
+            let result = match request["method"].as_str() {
+                Some("getVersion") => json!({ "solana-core": "1.18.0" }),
+                Some("getBalance") => {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    json!({ "context": { "slot": 1 }, "value": lamports })
+                }
+                method => panic!("unexpected RPC method {:?}", method),
+            };
+            json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] }).to_string()
+        });
+        (url, requests)
+    }
+
+    fn balance_stage(url: String, ttl: Duration) -> FeePayerBalanceStage {
+        let pool = RpcPool::new(&[url], Arc::new(crate::metrics::Metrics::new()));
+        FeePayerBalanceStage::new(Arc::new(pool), vec![], ttl)
+    }
+
+    fn unsigned(payer: &Pubkey, instructions: &[solana_sdk::instruction::Instruction]) -> TransactionCandidate {
+        let mut candidate = TransactionCandidate::new("");
+        candidate.transaction = Some(Transaction::new_unsigned(Message::new(instructions, Some(payer))));
+        candidate
+    }
+
+    #[test]
+    fn balance_must_cover_signature_fees_and_outgoing_transfers() {
+        let payer = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        // Two signers, 700 lamports leaving the payer. The 1_000_000 lamports
+        // leaving `other` are its own business.
+        let instructions = [
+            system_instruction::transfer(&payer, &recipient, 700),
+            system_instruction::transfer(&other, &recipient, 1_000_000),
+        ];
+        let required = 2 * LAMPORTS_PER_SIGNATURE + 700;
+
+        let (url, _) = balance_rpc(required);
+        assert!(balance_stage(url, Duration::ZERO).validate(&mut unsigned(&payer, &instructions)).is_ok());
+
+        let (url, _) = balance_rpc(required - 1);
+        let err = balance_stage(url, Duration::ZERO).validate(&mut unsigned(&payer, &instructions)).unwrap_err();
+        assert_eq!(
+            err.details(),
+            Some(json!({
+                "payer": payer.to_string(),
+                "balance_lamports": required - 1,
+                "required_lamports": required,
+                "shortfall_lamports": 1,
+            }))
+        );
+    }
+
+    #[test]
+    fn balances_are_cached_for_the_ttl() {
+        let payer = Pubkey::new_unique();
+        let instructions = [system_instruction::transfer(&payer, &Pubkey::new_unique(), 1)];
+
+        let (url, requests) = balance_rpc(1_000_000);
+        let cached = balance_stage(url, Duration::from_secs(60));
+        for _ in 0..3 {
+            cached.validate(&mut unsigned(&payer, &instructions)).unwrap();
+        }
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let (url, requests) = balance_rpc(1_000_000);
+        let expired = balance_stage(url, Duration::ZERO);
+        for _ in 0..3 {
+            expired.validate(&mut unsigned(&payer, &instructions)).unwrap();
+        }
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn balance_lookup_failure_lets_the_transaction_through() {
+        let payer = Pubkey::new_unique();
+        let instructions = [system_instruction::transfer(&payer, &Pubkey::new_unique(), u64::MAX)];
+        let unreachable = balance_stage("http://127.0.0.1:1".to_string(), Duration::from_secs(60));
+        assert!(unreachable.validate(&mut unsigned(&payer, &instructions)).is_ok());
+    }
+}